@@ -0,0 +1,228 @@
+use std::cell::OnceCell;
+use std::fmt;
+
+use crate::backend::{BackendError, DefaultBackend};
+use crate::{AbcSession, Backend, Result};
+#[cfg(windows)]
+use crate::{UIElement, UiaBackend};
+
+/// Text contained in the title of every Client4 window
+pub const ABC_WINDOW_TITLE: &str = "ABC Accounting Client";
+
+/// A running Client4 instance, as listed by [`list_abc_windows`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbcWindow<E = <DefaultBackend as Backend>::Element> {
+    /// The Client4 window itself
+    pub element: E,
+    /// Id of the Client4 process the window belongs to
+    pub process_id: u32,
+    /// The full title of the window
+    pub title: String,
+    /// The user logged into this instance, if any
+    pub user: Option<String>,
+}
+
+/// Which Client4 window to drive when several are open, eg. on a terminal server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowSelector {
+    /// The first Client4 window found, which is what [`crate::ensure_abc`] returns
+    First,
+    /// The window of the Client4 process with this id
+    ProcessId(u32),
+    /// The first window whose title contains this text
+    Title(String),
+    /// The first window this user is logged into, ignoring case
+    User(String),
+}
+
+impl WindowSelector {
+    fn matches<E>(&self, window: &AbcWindow<E>) -> bool {
+        match self {
+            WindowSelector::First => true,
+            WindowSelector::ProcessId(process_id) => window.process_id == *process_id,
+            WindowSelector::Title(title) => window.title.contains(title.as_str()),
+            WindowSelector::User(user) => window
+                .user
+                .as_ref()
+                .is_some_and(|logged_in| logged_in.eq_ignore_ascii_case(user)),
+        }
+    }
+}
+
+impl fmt::Display for WindowSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WindowSelector::First => write!(f, "any Client4 window"),
+            WindowSelector::ProcessId(process_id) => write!(f, "process {process_id}"),
+            WindowSelector::Title(title) => write!(f, "a title containing '{title}'"),
+            WindowSelector::User(user) => write!(f, "user '{user}'"),
+        }
+    }
+}
+
+/// List every running Client4 instance along with the user logged into it. Finding the user
+/// opens the login screen of each instance, see [`crate::current_user`]
+///
+/// # Errors
+///
+/// Return `Err(AbcError::Automation)` if a window or its login screen cannot be read
+#[cfg(windows)]
+pub fn list_abc_windows() -> Result<Vec<AbcWindow>> {
+    AbcSession::new(UiaBackend::new()?).list_abc_windows()
+}
+
+/// Find the Client4 window picked by `selector`. The window can be handed to any of the free
+/// functions, which then only look for screens and popups of that Client4 instance
+///
+/// # Errors
+///
+/// Return `Err(AbcError::Automation)` if no Client4 window matches `selector`
+#[cfg(windows)]
+pub fn find_abc_window(selector: &WindowSelector) -> Result<UIElement> {
+    let mut session = AbcSession::new(UiaBackend::new()?);
+    Ok(session.select_window(selector)?.clone())
+}
+
+#[cfg(windows)]
+impl AbcSession {
+    /// Start a session on the Client4 window picked by `selector`. See [`find_abc_window`]
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Automation)` if UI Automation cannot be initialized or if no
+    /// Client4 window matches `selector`
+    pub fn connect_to(selector: &WindowSelector) -> Result<Self> {
+        let mut session = AbcSession::new(UiaBackend::new()?);
+        session.select_window(selector)?;
+        Ok(session)
+    }
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Same as [`list_abc_windows`]. The session keeps driving the window it was using before
+    pub fn list_abc_windows(&mut self) -> Result<Vec<AbcWindow<B::Element>>> {
+        let previous = self.abc_window.take();
+        let windows = self.abc_windows(true);
+        self.use_window(previous);
+        windows
+    }
+
+    /// Switch the session to the Client4 window picked by `selector`. From then on, the session
+    /// only looks for screens and popups of that Client4 instance. Users are only read when
+    /// selecting by [`WindowSelector::User`]
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Automation)` if no Client4 window matches `selector`, in which case
+    /// the session keeps its current window
+    pub fn select_window(&mut self, selector: &WindowSelector) -> Result<&B::Element> {
+        let previous = self.abc_window.take();
+        let windows = self.abc_windows(matches!(selector, WindowSelector::User(_)));
+        let selected = windows.map(|windows| windows.into_iter().find(|w| selector.matches(w)));
+        match selected {
+            Ok(Some(window)) => {
+                self.use_window(Some(window.element));
+                self.user = window.user;
+                self.abc_window()
+            }
+            Ok(None) => {
+                self.use_window(previous);
+                Err(BackendError::not_found(format!("no Client4 window matches {selector}")).into())
+            }
+            Err(e) => {
+                self.use_window(previous);
+                Err(e)
+            }
+        }
+    }
+
+    fn abc_windows(&mut self, read_users: bool) -> Result<Vec<AbcWindow<B::Element>>> {
+        let mut windows = Vec::new();
+        for element in self.backend.find_windows(ABC_WINDOW_TITLE)? {
+            let user = match read_users {
+                true => {
+                    self.use_window(Some(element.clone()));
+                    let login_screen = self.load_login_screen()?;
+                    let user = self.read_user(&login_screen)?;
+                    self.close_login_screen()?;
+                    user
+                }
+                false => None,
+            };
+            windows.push(AbcWindow {
+                process_id: self.backend.process_id(&element)?,
+                title: self.backend.name(&element)?,
+                element,
+                user,
+            });
+        }
+        Ok(windows)
+    }
+
+    /// Drive `window`, scoping the backend to its process. `None` goes back to looking up the
+    /// first Client4 window the next time it is needed
+    pub(crate) fn use_window(&mut self, window: Option<B::Element>) {
+        let process_id = window
+            .as_ref()
+            .and_then(|window| self.backend.process_id(window).ok());
+        self.backend.scope_to_process(process_id);
+        self.abc_window = window.map(OnceCell::from).unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WindowSelector;
+    use crate::backend::FakeBackend;
+    use crate::{AbcSession, Backend, LoginScreen, Timing};
+
+    const LOGIN_TITLE: &str = <LoginScreen>::TITLE;
+
+    #[test]
+    fn test_select_window() {
+        let fake = FakeBackend::new();
+        for (process_id, store) in [(100, "Store 1"), (200, "Store 2")] {
+            let window = fake.add_screen(&format!("ABC Accounting Client - {store}"));
+            fake.set_process_id(window, process_id);
+        }
+        fake.on_keys("{F10}*", |fake, window, _| {
+            let process_id = fake.process_id(&window).unwrap();
+            let user = if process_id == 100 {
+                "CLERK"
+            } else {
+                "SERVICE"
+            };
+            let login_screen = fake.add_screen(LOGIN_TITLE);
+            fake.add_text_boxes(login_screen, &["", "", user]);
+            fake.set_process_id(login_screen, process_id);
+        });
+        fake.on_keys("{Esc}", |fake, _, _| {
+            fake.close(fake.find_screen(LOGIN_TITLE).unwrap());
+        });
+
+        let mut session = AbcSession::new(fake).with_timing(Timing::default().with_scale(0.1));
+        let windows = session.list_abc_windows().unwrap();
+        let found: Vec<_> = windows
+            .iter()
+            .map(|w| (w.process_id, w.user.as_deref()))
+            .collect();
+        assert_eq!(found, [(100, Some("CLERK")), (200, Some("SERVICE"))]);
+        // Reading the users leaves no login screen open behind
+        session.use_window(None);
+        assert!(session.backend().find_screen(LOGIN_TITLE).is_err());
+
+        session
+            .select_window(&WindowSelector::User("service".to_string()))
+            .unwrap();
+        assert_eq!(session.current_user().unwrap().as_deref(), Some("SERVICE"));
+        session
+            .select_window(&WindowSelector::Title("Store 1".to_string()))
+            .unwrap();
+        assert_eq!(session.current_user().unwrap().as_deref(), Some("CLERK"));
+
+        assert!(session
+            .select_window(&WindowSelector::ProcessId(300))
+            .is_err());
+        assert_eq!(session.current_user().unwrap().as_deref(), Some("CLERK"));
+    }
+}
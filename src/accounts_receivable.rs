@@ -1,6 +1,12 @@
-use crate::{create_matcher_wrapper, inventory::Item, wait, SHORT_WAIT_MS};
+use crate::keys::{Key, Keys};
+use crate::trace::traced;
+use crate::{
+    inventory::Item, AbcError, AbcSession, Backend, InvoicesScreen, Money, Result, SECONDS,
+};
+#[cfg(windows)]
+use crate::{UIElement, UiaBackend};
 use csv::ReaderBuilder;
-use uiautomation::{types::UIProperty, UIAutomation, UIElement};
+use tracing::{debug_span, info_span};
 
 /// An *invoice* is the record of a transaction from the Accounts Receivable screen in ABC
 #[derive(Debug, PartialEq)]
@@ -32,15 +38,15 @@ impl Invoice {
 
     /// Parse tabbed output from the 3-13 ABC report into a list of `Invoices`
     ///
-    /// # Arguments 
+    /// # Arguments
     ///
-    /// * `tsv_txt` - The string value of the TabbedOutput.tsv file generated by the 3-13 report 
+    /// * `tsv_txt` - The string value of the TabbedOutput.tsv file generated by the 3-13 report
     ///
-    /// # Returns 
+    /// # Returns
     ///
     /// Will return a list of `Invoices` parsed from `tsv_txt` in the order they appear in the text
     ///
-    /// # Errors 
+    /// # Errors
     ///
     /// Will return `csv::Error` if `tsv_txt` cannot be parsed. This is likely because the text is
     /// not properly formatted tab separated values
@@ -94,7 +100,7 @@ impl Invoice {
             }
 
             if let Some(col) = record.get(8) {
-                item.list = col.to_string().parse().unwrap_or(0f32) / quantity as f32;
+                item.list = col.parse::<Money>().unwrap_or_default().per_unit(quantity);
             }
 
             if item != Item::new() {
@@ -106,8 +112,8 @@ impl Invoice {
     }
 }
 
-/// Control the ABC Client4 window to load the Invoices records screen, and return the `UIElement`
-/// that represents that screen.
+/// Control the ABC Client4 window to load the Invoices records screen, and return the
+/// [`InvoicesScreen`] that represents that screen.
 ///
 /// # Arguments
 ///
@@ -116,38 +122,26 @@ impl Invoice {
 ///
 /// # Returns
 ///
-/// If successful, return the `UIElement` that represents the Invoices screen. This is a control
+/// If successful, return the [`InvoicesScreen`] that represents the Invoices screen. This is a control
 /// inside Client4 whose name contains "Sales - Invoices (R)"
 ///
 /// If any key combos fail to send or if the invoices screen cannot be found, return
-/// `Err(uiautomation::Error)`
+/// `Err(AbcError)`
 ///
 /// # Errors
 ///
 /// If any key combos fail to send or if the invoices screen cannot be found, return
-/// `Err(uiautomation::Error)`
-pub fn load_invoices_screen(abc_window: &UIElement) -> uiautomation::Result<UIElement> {
-    let automation = UIAutomation::new()?;
-
-    if let Ok(invoices_screen) = create_matcher_wrapper(&automation)?
-        .contains_name("Sales - Invoices (R)")
-        .find_first()
-    {
-        return Ok(invoices_screen);
-    }
-
-    abc_window.send_keys("{F10}R", SHORT_WAIT_MS * 3)?;
-
-    create_matcher_wrapper(&automation)?
-        .contains_name("Sales - Invoices (R)")
-        .find_first()
+/// `Err(AbcError)`
+#[cfg(windows)]
+pub fn load_invoices_screen(abc_window: &UIElement) -> Result<InvoicesScreen> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).load_invoices_screen()
 }
 
 /// Loads an invoice identified by its number into the provided invoices window.
 ///
 /// # Arguments
 ///
-/// * `invoices_window` - A reference to a UI element (e.g., ABC (R) screen or Accounts Receivable Screen)
+/// * `invoices_window` - A reference to the ABC (R) screen or Accounts Receivable Screen
 ///   containing invoice details.
 /// * `invoice_num` - The unique identifier of the invoice to be loaded.
 ///
@@ -175,17 +169,9 @@ pub fn load_invoices_screen(abc_window: &UIElement) -> uiautomation::Result<UIEl
 ///     Err(err) => println!("Error: {}", err),
 /// }
 /// ```
-pub fn load_invoice(invoices_window: &UIElement, invoice_num: u64) -> uiautomation::Result<()> {
-    let automation = UIAutomation::new()?;
-
-    let invoice_num_control = create_matcher_wrapper(&automation)?
-        .classname("ThunderRT6TextBox")
-        .from(invoices_window.to_owned())
-        .find_first()?;
-    invoice_num_control.click()?;
-    invoice_num_control.send_keys(&format!("{}{{enter}}", invoice_num), SHORT_WAIT_MS)?;
-
-    Ok(())
+#[cfg(windows)]
+pub fn load_invoice(invoices_window: &InvoicesScreen, invoice_num: u64) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).load_invoice(invoices_window, invoice_num)
 }
 
 /// Sends an invoice to JDF (John Deere Financial) and checks if the operation was successful.
@@ -220,53 +206,16 @@ pub fn load_invoice(invoices_window: &UIElement, invoice_num: u64) -> uiautomati
 ///     Err(err) => println!("Error: {}", err),
 /// }
 /// ```
-pub fn send_invoice_to_jdf(
-    invoices_window: &UIElement,
-    invoice_num: u64,
-) -> uiautomation::Result<bool> {
-    let automation = UIAutomation::new()?;
-
-    load_invoice(invoices_window, invoice_num)?;
-    let all_text_boxes = create_matcher_wrapper(&automation)?
-        .from(invoices_window.to_owned())
-        .classname("ThunderRT6TextBox")
-        .find_all()?;
-    let paid_control = match all_text_boxes.get(29) {
-        Some(c) => c,
-        None => return Err(uiautomation::Error::new(2, "could not find paid control")),
-    };
-    let paid_control_value = paid_control
-        .get_property_value(UIProperty::ValueValue)?
-        .get_string()?;
-    if paid_control_value != String::new() {
-        return Ok(true);
-    }
-
-    invoices_window.send_keys("{F9}7R", SHORT_WAIT_MS * 3)?;
-    wait(2000);
-
-    let invoice_num_control = create_matcher_wrapper(&automation)?
-        .classname("ThunderRT6TextBox")
-        .from(invoices_window.to_owned())
-        .find_first()?;
-    let invoice_num_control_value = invoice_num_control
-        .get_property_value(UIProperty::ValueValue)?
-        .get_string()?;
-    if invoice_num.to_string() == invoice_num_control_value {
-        invoices_window.send_keys("{enter}{esc}", SHORT_WAIT_MS * 3)?;
-        invoices_window.hold_send_keys("{ctrl}", "n", SHORT_WAIT_MS * 3)?;
-        invoices_window.send_keys("{right}{enter}", SHORT_WAIT_MS * 3)?;
-        return Ok(false);
-    }
-
-    Ok(true)
+#[cfg(windows)]
+pub fn send_invoice_to_jdf(invoices_window: &InvoicesScreen, invoice_num: u64) -> Result<bool> {
+    AbcSession::new(UiaBackend::new()?).send_invoice_to_jdf(invoices_window, invoice_num)
 }
 
 /// Checks if an invoice is fully paid by comparing values extracted from specific UI elements.
 ///
 /// # Arguments
 ///
-/// * `invoices_window` - A reference to a UI element containing invoice details.
+/// * `invoices_window` - A reference to the ABC (R) screen containing invoice details.
 /// * `invoice_num` - The unique identifier of the invoice being checked.
 ///
 /// # Returns
@@ -293,48 +242,172 @@ pub fn send_invoice_to_jdf(
 ///     Err(err) => println!("Error: {}", err),
 /// }
 /// ```
-pub fn is_invoice_fully_paid(
-    invoices_window: &UIElement,
-    invoice_num: u64,
-) -> uiautomation::Result<bool> {
-    let automation = UIAutomation::new()?;
-
-    load_invoice(invoices_window, invoice_num)?;
-    let all_text_boxes = create_matcher_wrapper(&automation)?
-        .from(invoices_window.to_owned())
-        .classname("ThunderRT6TextBox")
-        .find_all()?;
-    let paid_control = match all_text_boxes.get(29) {
-        Some(c) => c,
-        None => return Err(uiautomation::Error::new(2, "could not find paid control")),
-    };
-    let total_control = match all_text_boxes.get(38) {
-        Some(c) => c,
-        None => {
-            return Err(uiautomation::Error::new(
-                2,
-                "could not find invoice total control",
-            ))
-        }
-    };
-    let paid_control_value = paid_control
-        .get_property_value(UIProperty::ValueValue)?
-        .get_string()?;
-    let total_control_value = total_control
-        .get_property_value(UIProperty::ValueValue)?
-        .get_string()?;
-
-    Ok(paid_control_value == total_control_value)
+#[cfg(windows)]
+pub fn is_invoice_fully_paid(invoices_window: &InvoicesScreen, invoice_num: u64) -> Result<bool> {
+    AbcSession::new(UiaBackend::new()?).is_invoice_fully_paid(invoices_window, invoice_num)
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Find the ThunderRT6TextBox on the invoices screen that holds the invoice number
+    fn invoice_num_control(
+        &self,
+        invoices_window: &InvoicesScreen<B::Element>,
+    ) -> Result<B::Element> {
+        self.text_box_at(
+            invoices_window.element(),
+            self.field_maps.invoices.index("invoice_number")?,
+        )
+    }
+
+    /// Same as [`load_invoices_screen`]
+    pub fn load_invoices_screen(&self) -> Result<InvoicesScreen<B::Element>> {
+        traced(
+            debug_span!("load_screen", screen = InvoicesScreen::<B::Element>::TITLE),
+            || {
+                if let Ok(invoices_screen) = InvoicesScreen::find(&self.backend) {
+                    return Ok(invoices_screen);
+                }
+
+                let abc_window = self.abc_window()?;
+                self.send_keys(
+                    abc_window,
+                    &Keys::new().key(Key::F(10)).text("R"),
+                    self.timing.short() * 3,
+                )?;
+
+                InvoicesScreen::find(&self.backend)
+            },
+        )
+    }
+
+    /// Same as [`load_invoice`]
+    pub fn load_invoice(
+        &self,
+        invoices_window: &InvoicesScreen<B::Element>,
+        invoice_num: u64,
+    ) -> Result<()> {
+        traced(debug_span!("load_invoice", invoice_num), || {
+            let invoice_num_control = self.invoice_num_control(invoices_window)?;
+            self.backend.click(&invoice_num_control)?;
+            self.send_keys(
+                &invoice_num_control,
+                &Keys::new().text(&invoice_num.to_string()).key(Key::Enter),
+                self.timing.short(),
+            )?;
+
+            Ok(())
+        })
+    }
+
+    /// Same as [`send_invoice_to_jdf`]
+    pub fn send_invoice_to_jdf(
+        &self,
+        invoices_window: &InvoicesScreen<B::Element>,
+        invoice_num: u64,
+    ) -> Result<bool> {
+        traced(info_span!("send_invoice_to_jdf", invoice_num), || {
+            self.load_invoice(invoices_window, invoice_num)?;
+            let paid_control_value = self.read_text_box_value(
+                invoices_window.element(),
+                self.field_maps.invoices.index("paid")?,
+            )?;
+            if paid_control_value != String::new() {
+                return Ok(true);
+            }
+
+            self.send_keys(
+                invoices_window.element(),
+                &Keys::new().key(Key::F(9)).text("7R"),
+                self.timing.short() * 3,
+            )?;
+
+            // Client4 moves off of the invoice once it has been sent. If it is still showing the
+            // invoice, sending failed
+            let invoice_num_control = self.invoice_num_control(invoices_window)?;
+            let sent = self.wait_for(
+                "send_invoice_to_jdf",
+                2 * SECONDS,
+                "the invoice to be sent to JDF",
+                || Ok(self.backend.value(&invoice_num_control)? != invoice_num.to_string()),
+            );
+            if let Err(AbcError::Timeout { .. }) = sent {
+                self.send_keys(
+                    invoices_window.element(),
+                    &Keys::new().key(Key::Enter).key(Key::Esc),
+                    self.timing.short() * 3,
+                )?;
+                self.hold_send_keys(
+                    invoices_window.element(),
+                    Key::Ctrl,
+                    &Keys::new().text("n"),
+                    self.timing.short() * 3,
+                )?;
+                self.send_keys(
+                    invoices_window.element(),
+                    &Keys::new().key(Key::Right).key(Key::Enter),
+                    self.timing.short() * 3,
+                )?;
+                return Ok(false);
+            }
+            sent?;
+
+            Ok(true)
+        })
+    }
+
+    /// Same as [`is_invoice_fully_paid`]
+    pub fn is_invoice_fully_paid(
+        &self,
+        invoices_window: &InvoicesScreen<B::Element>,
+        invoice_num: u64,
+    ) -> Result<bool> {
+        traced(debug_span!("is_invoice_fully_paid", invoice_num), || {
+            self.load_invoice(invoices_window, invoice_num)?;
+            let fields = &self.field_maps.invoices;
+            let paid_control_value =
+                self.read_text_box_value(invoices_window.element(), fields.index("paid")?)?;
+            let total_control_value =
+                self.read_text_box_value(invoices_window.element(), fields.index("total")?)?;
+
+            Ok(paid_control_value == total_control_value)
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use chrono::NaiveDate;
 
+    use crate::backend::FakeBackend;
     use crate::inventory::Item;
+    use crate::{AbcSession, Backend, InvoicesScreen, Money, Timing};
 
     use super::Invoice;
 
+    #[test]
+    fn test_send_invoice_to_jdf() {
+        let fake = FakeBackend::new();
+        fake.add_screen("ABC Accounting Client");
+        let screen = fake.add_screen("Sales - Invoices (R)");
+        fake.add_text_boxes(screen, &[""; 39]);
+        // Only invoice 540970 goes through, after which Client4 clears the invoice screen
+        fake.on_keys("{F9}7R", |fake, _, _| {
+            let screen = fake.find_screen("Sales - Invoices (R)").unwrap();
+            if fake.text_box_values(&screen).unwrap()[0] == "540970" {
+                fake.set_text_box(screen, 0, "");
+            }
+        });
+        let session = AbcSession::new(fake).with_timing(Timing::default().with_scale(0.1));
+        let invoices_window = InvoicesScreen::find(session.backend()).unwrap();
+
+        assert!(session
+            .send_invoice_to_jdf(&invoices_window, 540970)
+            .unwrap());
+        assert!(!session
+            .send_invoice_to_jdf(&invoices_window, 540971)
+            .unwrap());
+    }
+
     #[test]
     fn test_from_tsv() {
         let tsv_txt = "	  INVOICE #540970			STORE NAME		PO BOX 123		123 STREET ADR		CITY ST  12345		(123)456-7890													Cash Sale																		-------------------------------------------------------------------------------	CUST.CODE		CUST.P/O#		SHIP VIA		SLS		TAX CODE		TERMS		  INVOICE	DATE	.CASH				PA		CASH	12/ 6/24		-------------------------------------------------------------------------------		QUANTITY  ITEM #     DESCRIPTION                                      SELL PRICE       AMOUNT	
@@ -402,8 +475,8 @@ X1234 Auth:012345 Seq:1234567890 x____________________________	Amount:	121.90";
                 Item {
                     sku: "PURSTRATEGYH".to_string(),
                     description: Some("STRATEGY HEALTHY EDGE".to_string()),
-                    list: 25.0,
-                    cost: 0.0,
+                    list: "25.00".parse().unwrap(),
+                    cost: Money::default(),
                     vendor_id: None,
                     upc: None,
                 },
@@ -413,8 +486,8 @@ X1234 Auth:012345 Seq:1234567890 x____________________________	Amount:	121.90";
                 Item {
                     sku: "SS137386".to_string(),
                     description: Some("3' PRM MINI FLAKE SHAVINGS BEDDING".to_string()),
-                    list: 10.0,
-                    cost: 0.0,
+                    list: "10.00".parse().unwrap(),
+                    cost: Money::default(),
                     vendor_id: None,
                     upc: None,
                 },
@@ -424,8 +497,8 @@ X1234 Auth:012345 Seq:1234567890 x____________________________	Amount:	121.90";
                 Item {
                     sku: "SS379776".to_string(),
                     description: Some("QT GLAZING COMPOUND".to_string()),
-                    list: 11.24,
-                    cost: 0.0,
+                    list: "11.24".parse().unwrap(),
+                    cost: Money::default(),
                     vendor_id: None,
                     upc: None,
                 },
@@ -435,8 +508,8 @@ X1234 Auth:012345 Seq:1234567890 x____________________________	Amount:	121.90";
                 Item {
                     sku: "FAST".to_string(),
                     description: Some("MIDWEST FASTENERS".to_string()),
-                    list: 0.20,
-                    cost: 0.0,
+                    list: "0.20".parse().unwrap(),
+                    cost: Money::default(),
                     vendor_id: None,
                     upc: None,
                 },
@@ -446,8 +519,8 @@ X1234 Auth:012345 Seq:1234567890 x____________________________	Amount:	121.90";
                 Item {
                     sku: "SS764720".to_string(),
                     description: Some("GAL RAINX WINDOW WASH FLUID".to_string()),
-                    list: 5.49,
-                    cost: 0.0,
+                    list: "5.49".parse().unwrap(),
+                    cost: Money::default(),
                     vendor_id: None,
                     upc: None,
                 },
@@ -457,8 +530,8 @@ X1234 Auth:012345 Seq:1234567890 x____________________________	Amount:	121.90";
                 Item {
                     sku: "PA".to_string(),
                     description: Some("SALES TAX on $115.00".to_string()),
-                    list: 6.9,
-                    cost: 0.0,
+                    list: "6.90".parse().unwrap(),
+                    cost: Money::default(),
                     vendor_id: None,
                     upc: None,
                 },
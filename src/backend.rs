@@ -0,0 +1,222 @@
+mod fake;
+#[cfg(windows)]
+mod uia;
+
+pub use fake::{FakeBackend, FakeElement};
+#[cfg(windows)]
+pub use uia::UiaBackend;
+
+use std::fmt::{self, Debug};
+
+use serde::{Deserialize, Serialize};
+
+/// The backend sessions drive Client4 with unless given another one: [`UiaBackend`] on Windows,
+/// and [`FakeBackend`] everywhere else, where Client4 cannot run and only tests can
+#[cfg(windows)]
+pub(crate) type DefaultBackend = UiaBackend;
+#[cfg(not(windows))]
+pub(crate) type DefaultBackend = FakeBackend;
+
+/// Why a [`Backend`] call failed. Errors of the underlying automation library, such as those of
+/// [`UiaBackend`], are converted into one of these, so the crate does not depend on that library
+/// outside of Windows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendError {
+    not_found: bool,
+    message: String,
+}
+
+impl BackendError {
+    /// An element, such as a screen or text box, could not be found
+    pub fn not_found(message: impl Into<String>) -> Self {
+        BackendError {
+            not_found: true,
+            message: message.into(),
+        }
+    }
+
+    /// Any other failure, eg. keys that could not be sent
+    pub fn other(message: impl Into<String>) -> Self {
+        BackendError {
+            not_found: false,
+            message: message.into(),
+        }
+    }
+
+    /// Whether the call failed because an element could not be found
+    pub fn is_not_found(&self) -> bool {
+        self.not_found
+    }
+
+    /// The same kind of error with `message` in place of its own, eg. to keep a password that the
+    /// original message may quote out of it
+    pub fn with_message(&self, message: impl Into<String>) -> Self {
+        BackendError {
+            not_found: self.not_found,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+/// Screen coordinates of the edges of an element, in pixels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Bounds {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl fmt::Display for Bounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "({}, {}) to ({}, {})",
+            self.left, self.top, self.right, self.bottom
+        )
+    }
+}
+
+/// The small set of UI operations the rest of the crate needs in order to drive Client4. Every
+/// screen, text box, and popup is handed around as a `Self::Element`, so the same workflow code
+/// can run against a live Windows desktop ([`UiaBackend`]) or a scripted in-memory Client4
+/// ([`FakeBackend`])
+pub trait Backend {
+    /// Handle to a single element of the UI tree, such as a screen, text box, or popup
+    type Element: Clone + Debug;
+
+    /// Find the first element under the desktop whose name contains `name`. This is how the
+    /// Client4 window and each of its screens are located
+    ///
+    /// # Errors
+    ///
+    /// Return an error for which [`BackendError::is_not_found`] holds if no matching element can
+    /// be found
+    fn find_screen(&self, name: &str) -> Result<Self::Element, BackendError>;
+
+    /// Check whether an element whose name contains `name` is open right now, without waiting
+    /// for one to appear. See [`Backend::find_screen`]
+    fn screen_exists(&self, name: &str) -> Result<bool, BackendError> {
+        Ok(self.find_screen(name).is_ok())
+    }
+
+    /// Find the first element under the desktop whose name is exactly `name`, for screens whose
+    /// title is too short to look for by part of it. See [`Backend::find_screen`]
+    fn find_screen_named(&self, name: &str) -> Result<Self::Element, BackendError>;
+
+    /// Check whether an element whose name is exactly `name` is open right now, without waiting
+    /// for one to appear. See [`Backend::find_screen_named`]
+    fn screen_named_exists(&self, name: &str) -> Result<bool, BackendError> {
+        Ok(self.find_screen_named(name).is_ok())
+    }
+
+    /// List every open window whose name contains `name`, in any process and without waiting
+    /// for one to appear. This is how every running Client4 instance is found
+    fn find_windows(&self, name: &str) -> Result<Vec<Self::Element>, BackendError>;
+
+    /// Read the id of the process an element belongs to
+    fn process_id(&self, element: &Self::Element) -> Result<u32, BackendError>;
+
+    /// Only find screens and popups that belong to the process `process_id`, or to any process
+    /// if it is `None`. Used to drive one of several Client4 instances
+    fn scope_to_process(&mut self, process_id: Option<u32>);
+
+    /// Look for a ThunderRT6FormDC dialog whose title contains `title`, waiting at most
+    /// `timeout_ms` for it to appear
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The element to search beneath. `None` searches the entire desktop
+    /// * `title` - Part of the title of the dialog to look for
+    /// * `timeout_ms` - How long to wait for the dialog to appear
+    ///
+    /// # Returns
+    ///
+    /// `Ok(None)` if the dialog did not appear before the timeout
+    fn find_popup(
+        &self,
+        from: Option<&Self::Element>,
+        title: &str,
+        timeout_ms: u64,
+    ) -> Result<Option<Self::Element>, BackendError>;
+
+    /// List every ThunderRT6TextBox beneath `screen` in the order Client4 exposes them. This is
+    /// the order the crate's textbox indices refer to
+    fn text_boxes(&self, screen: &Self::Element) -> Result<Vec<Self::Element>, BackendError>;
+
+    /// Same as [`Backend::text_boxes`], but searching for at most `timeout_ms` instead of the
+    /// timeout from [`Backend::set_timeout`]. Backends that never wait can ignore it
+    fn text_boxes_within(
+        &self,
+        screen: &Self::Element,
+        _timeout_ms: u64,
+    ) -> Result<Vec<Self::Element>, BackendError> {
+        self.text_boxes(screen)
+    }
+
+    /// List the values of every ThunderRT6TextBox beneath `screen`. See [`Backend::text_boxes`]
+    fn text_box_values(&self, screen: &Self::Element) -> Result<Vec<String>, BackendError> {
+        self.text_boxes(screen)?
+            .iter()
+            .map(|text_box| self.value(text_box))
+            .collect()
+    }
+
+    /// List every element beneath `element` whose class name is `classname`, such as the labels
+    /// and buttons of a dialog, without waiting for any to appear
+    fn descendants(
+        &self,
+        element: &Self::Element,
+        classname: &str,
+    ) -> Result<Vec<Self::Element>, BackendError>;
+
+    /// List the direct children of `element` in the order UI Automation exposes them
+    fn children(&self, element: &Self::Element) -> Result<Vec<Self::Element>, BackendError>;
+
+    /// Read the class name of an element, eg. "ThunderRT6TextBox"
+    fn classname(&self, element: &Self::Element) -> Result<String, BackendError>;
+
+    /// Read the name of the UI Automation control type of an element, eg. "Edit" or "Button"
+    fn control_type(&self, element: &Self::Element) -> Result<String, BackendError>;
+
+    /// Read where an element is on screen
+    fn bounds(&self, element: &Self::Element) -> Result<Bounds, BackendError>;
+
+    /// Read the name (title) of an element
+    fn name(&self, element: &Self::Element) -> Result<String, BackendError>;
+
+    /// Read the value of an element. For text boxes this is the text they contain
+    fn value(&self, element: &Self::Element) -> Result<String, BackendError>;
+
+    /// Click on an element, giving it keyboard focus
+    fn click(&self, element: &Self::Element) -> Result<(), BackendError>;
+
+    /// Send a sequence of keystrokes to an element, pausing `interval_ms` between each key
+    fn send_keys(
+        &self,
+        element: &Self::Element,
+        keys: &str,
+        interval_ms: u64,
+    ) -> Result<(), BackendError>;
+
+    /// Set how long, in milliseconds, [`Backend::find_screen`] and [`Backend::text_boxes`] keep
+    /// searching before giving up. Backends that never wait can ignore this
+    fn set_timeout(&mut self, _timeout_ms: u64) {}
+
+    /// Send `keys` to an element while `holdkeys` (eg. "{Ctrl}") are held down
+    fn hold_send_keys(
+        &self,
+        element: &Self::Element,
+        holdkeys: &str,
+        keys: &str,
+        interval_ms: u64,
+    ) -> Result<(), BackendError>;
+}
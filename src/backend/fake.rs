@@ -0,0 +1,497 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::{Backend, BackendError, Bounds};
+
+/// Handle to an element owned by a [`FakeBackend`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FakeElement(usize);
+
+#[derive(Debug, Clone)]
+struct FakeNode {
+    classname: String,
+    name: String,
+    value: String,
+    bounds: Bounds,
+    process_id: Option<u32>,
+    parent: Option<usize>,
+    open: bool,
+}
+
+type Reaction = Rc<dyn Fn(&FakeBackend, FakeElement, &str)>;
+
+/// Scriptable, in-memory stand-in for Client4 that implements [`Backend`]. Screens, text boxes,
+/// and popups are added by hand, and reactions registered with [`FakeBackend::on_keys`] play the
+/// part of Client4 responding to keystrokes, so workflows can be tested without a Windows desktop.
+///
+/// Keys sent to a text box edit its value: `{Delete}` clears the box and literal characters are
+/// appended. Keys sent to a popup close it once all reactions have run.
+pub struct FakeBackend {
+    nodes: RefCell<Vec<FakeNode>>,
+    reactions: RefCell<Vec<(String, Reaction)>>,
+    sent_keys: RefCell<Vec<(FakeElement, String)>>,
+    scope: Option<u32>,
+}
+
+impl FakeBackend {
+    /// Create an empty desktop with nothing open on it
+    pub fn new() -> Self {
+        FakeBackend {
+            nodes: RefCell::new(vec![FakeNode {
+                classname: "#32769".to_string(),
+                name: "Desktop".to_string(),
+                value: String::new(),
+                bounds: Bounds::default(),
+                process_id: None,
+                parent: None,
+                open: true,
+            }]),
+            reactions: RefCell::new(Vec::new()),
+            sent_keys: RefCell::new(Vec::new()),
+            scope: None,
+        }
+    }
+
+    /// The desktop that every other element lives under
+    pub fn root(&self) -> FakeElement {
+        FakeElement(0)
+    }
+
+    /// Add an arbitrary element beneath `parent`
+    pub fn add_element(
+        &self,
+        parent: FakeElement,
+        classname: &str,
+        name: &str,
+        value: &str,
+    ) -> FakeElement {
+        let mut nodes = self.nodes.borrow_mut();
+        nodes.push(FakeNode {
+            classname: classname.to_string(),
+            name: name.to_string(),
+            value: value.to_string(),
+            bounds: Bounds::default(),
+            process_id: None,
+            parent: Some(parent.0),
+            open: true,
+        });
+        FakeElement(nodes.len() - 1)
+    }
+
+    /// Add a Client4 screen (or the Client4 window itself) to the desktop
+    pub fn add_screen(&self, name: &str) -> FakeElement {
+        self.add_element(self.root(), "ThunderRT6Form", name, "")
+    }
+
+    /// Add one ThunderRT6TextBox beneath `screen` for each value in `values`, in order
+    pub fn add_text_boxes(&self, screen: FakeElement, values: &[&str]) -> Vec<FakeElement> {
+        values
+            .iter()
+            .map(|value| self.add_element(screen, "ThunderRT6TextBox", "", value))
+            .collect()
+    }
+
+    /// Open a ThunderRT6FormDC dialog titled `title` on the desktop
+    pub fn add_popup(&self, title: &str) -> FakeElement {
+        self.add_element(self.root(), "ThunderRT6FormDC", title, "")
+    }
+
+    /// Open a dialog like [`FakeBackend::add_popup`], with a ThunderRT6Label for each line of
+    /// `message` and a ThunderRT6CommandButton for each of `buttons`
+    pub fn add_popup_with(&self, title: &str, message: &str, buttons: &[&str]) -> FakeElement {
+        let popup = self.add_popup(title);
+        for line in message.lines() {
+            self.add_element(popup, "ThunderRT6Label", line, "");
+        }
+        for button in buttons {
+            self.add_element(popup, "ThunderRT6CommandButton", button, "");
+        }
+        popup
+    }
+
+    /// Close an element and everything beneath it so it can no longer be found
+    pub fn close(&self, element: FakeElement) {
+        self.nodes.borrow_mut()[element.0].open = false;
+    }
+
+    /// Overwrite the value of an element
+    pub fn set_value(&self, element: FakeElement, value: &str) {
+        self.nodes.borrow_mut()[element.0].value = value.to_string();
+    }
+
+    /// Move an element to `bounds` on screen. Elements start out at `Bounds::default()`
+    pub fn set_bounds(&self, element: FakeElement, bounds: Bounds) {
+        self.nodes.borrow_mut()[element.0].bounds = bounds;
+    }
+
+    /// Make `element` and everything beneath it belong to the process `process_id`. Elements
+    /// belong to process 0 until this is called
+    pub fn set_process_id(&self, element: FakeElement, process_id: u32) {
+        self.nodes.borrow_mut()[element.0].process_id = Some(process_id);
+    }
+
+    /// Overwrite the value of the text box at `index` beneath `screen`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `screen` has no text box at `index`
+    pub fn set_text_box(&self, screen: FakeElement, index: usize, value: &str) {
+        let text_box = self.text_boxes(&screen).unwrap()[index];
+        self.set_value(text_box, value);
+    }
+
+    /// Register a reaction that runs whenever keys containing `pattern` are sent to any element.
+    /// The reaction receives the backend, the element the keys were sent to, and the full key
+    /// sequence
+    pub fn on_keys(
+        &self,
+        pattern: &str,
+        reaction: impl Fn(&FakeBackend, FakeElement, &str) + 'static,
+    ) {
+        self.reactions
+            .borrow_mut()
+            .push((pattern.to_string(), Rc::new(reaction)));
+    }
+
+    /// Every key sequence sent so far, along with the element it was sent to
+    pub fn sent_keys(&self) -> Vec<(FakeElement, String)> {
+        self.sent_keys.borrow().clone()
+    }
+
+    fn node(&self, element: FakeElement) -> FakeNode {
+        self.nodes.borrow()[element.0].clone()
+    }
+
+    fn is_open(&self, element: FakeElement) -> bool {
+        let nodes = self.nodes.borrow();
+        let mut current = Some(element.0);
+        while let Some(i) = current {
+            if !nodes[i].open {
+                return false;
+            }
+            current = nodes[i].parent;
+        }
+        true
+    }
+
+    fn in_scope(&self, element: FakeElement) -> bool {
+        match self.scope {
+            Some(process_id) => self.process_id(&element).unwrap() == process_id,
+            None => true,
+        }
+    }
+
+    fn is_beneath(&self, element: FakeElement, ancestor: FakeElement) -> bool {
+        let nodes = self.nodes.borrow();
+        let mut current = nodes[element.0].parent;
+        while let Some(i) = current {
+            if i == ancestor.0 {
+                return true;
+            }
+            current = nodes[i].parent;
+        }
+        false
+    }
+
+    fn find_all(
+        &self,
+        from: FakeElement,
+        predicate: impl Fn(&FakeNode) -> bool,
+    ) -> Vec<FakeElement> {
+        let len = self.nodes.borrow().len();
+        (0..len)
+            .map(FakeElement)
+            .filter(|&e| self.is_beneath(e, from) && self.is_open(e) && predicate(&self.node(e)))
+            .collect()
+    }
+
+    fn record_keys(&self, element: FakeElement, keys: &str) {
+        self.sent_keys
+            .borrow_mut()
+            .push((element, keys.to_string()));
+        let reactions: Vec<Reaction> = self
+            .reactions
+            .borrow()
+            .iter()
+            .filter(|(pattern, _)| keys.contains(pattern.as_str()))
+            .map(|(_, reaction)| reaction.clone())
+            .collect();
+        for reaction in reactions {
+            reaction(self, element, keys);
+        }
+    }
+}
+
+impl Default for FakeBackend {
+    fn default() -> Self {
+        FakeBackend::new()
+    }
+}
+
+/// Apply `keys` to the text of a text box the way typing them would
+fn type_into(value: &mut String, keys: &str) {
+    let mut chars = keys.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '{' {
+            value.push(c);
+            continue;
+        }
+        let mut key = String::new();
+        // `{{}`, `{}}`, `{(}` and `{)}` are the escaped forms of literal braces and parentheses
+        if let Some(&first) = chars.peek() {
+            key.push(first);
+            chars.next();
+        }
+        for c in chars.by_ref() {
+            if c == '}' {
+                break;
+            }
+            key.push(c);
+        }
+        match key.to_lowercase().as_str() {
+            "{" | "}" | "(" | ")" => value.push_str(&key),
+            "delete" | "del" => value.clear(),
+            _ => (),
+        }
+    }
+}
+
+impl Backend for FakeBackend {
+    type Element = FakeElement;
+
+    fn find_screen(&self, name: &str) -> Result<FakeElement, BackendError> {
+        self.find_all(self.root(), |node| node.name.contains(name))
+            .into_iter()
+            .find(|&e| self.in_scope(e))
+            .ok_or_else(|| BackendError::not_found(format!("no screen named {name}")))
+    }
+
+    fn find_screen_named(&self, name: &str) -> Result<FakeElement, BackendError> {
+        self.find_all(self.root(), |node| node.name == name)
+            .into_iter()
+            .find(|&e| self.in_scope(e))
+            .ok_or_else(|| BackendError::not_found(format!("no screen named {name}")))
+    }
+
+    fn find_popup(
+        &self,
+        from: Option<&FakeElement>,
+        title: &str,
+        _timeout_ms: u64,
+    ) -> Result<Option<FakeElement>, BackendError> {
+        let from = from.copied().unwrap_or(self.root());
+        Ok(self
+            .find_all(from, |node| {
+                node.classname == "ThunderRT6FormDC" && node.name.contains(title)
+            })
+            .into_iter()
+            .find(|&e| self.in_scope(e)))
+    }
+
+    fn find_windows(&self, name: &str) -> Result<Vec<FakeElement>, BackendError> {
+        Ok(self.find_all(self.root(), |node| {
+            node.parent == Some(0) && node.name.contains(name)
+        }))
+    }
+
+    fn process_id(&self, element: &FakeElement) -> Result<u32, BackendError> {
+        let nodes = self.nodes.borrow();
+        let mut current = Some(element.0);
+        while let Some(i) = current {
+            if let Some(process_id) = nodes[i].process_id {
+                return Ok(process_id);
+            }
+            current = nodes[i].parent;
+        }
+        Ok(0)
+    }
+
+    fn scope_to_process(&mut self, process_id: Option<u32>) {
+        self.scope = process_id;
+    }
+
+    fn text_boxes(&self, screen: &FakeElement) -> Result<Vec<FakeElement>, BackendError> {
+        Ok(self.find_all(*screen, |node| node.classname == "ThunderRT6TextBox"))
+    }
+
+    fn descendants(
+        &self,
+        element: &FakeElement,
+        classname: &str,
+    ) -> Result<Vec<FakeElement>, BackendError> {
+        Ok(self.find_all(*element, |node| node.classname == classname))
+    }
+
+    fn children(&self, element: &FakeElement) -> Result<Vec<FakeElement>, BackendError> {
+        Ok(self.find_all(*element, |node| node.parent == Some(element.0)))
+    }
+
+    fn classname(&self, element: &FakeElement) -> Result<String, BackendError> {
+        Ok(self.node(*element).classname)
+    }
+
+    fn control_type(&self, element: &FakeElement) -> Result<String, BackendError> {
+        let control_type = match self.node(*element).classname.as_str() {
+            "ThunderRT6TextBox" => "Edit",
+            "ThunderRT6Label" => "Text",
+            "ThunderRT6CommandButton" => "Button",
+            "ThunderRT6Form" | "ThunderRT6FormDC" => "Window",
+            _ => "Pane",
+        };
+        Ok(control_type.to_string())
+    }
+
+    fn bounds(&self, element: &FakeElement) -> Result<Bounds, BackendError> {
+        Ok(self.node(*element).bounds)
+    }
+
+    fn name(&self, element: &FakeElement) -> Result<String, BackendError> {
+        Ok(self.node(*element).name)
+    }
+
+    fn value(&self, element: &FakeElement) -> Result<String, BackendError> {
+        Ok(self.node(*element).value)
+    }
+
+    fn click(&self, _element: &FakeElement) -> Result<(), BackendError> {
+        Ok(())
+    }
+
+    fn send_keys(
+        &self,
+        element: &FakeElement,
+        keys: &str,
+        _interval_ms: u64,
+    ) -> Result<(), BackendError> {
+        let classname = self.node(*element).classname;
+        if classname == "ThunderRT6TextBox" {
+            type_into(&mut self.nodes.borrow_mut()[element.0].value, keys);
+        }
+        self.record_keys(*element, keys);
+        if classname == "ThunderRT6FormDC" {
+            self.close(*element);
+        }
+        Ok(())
+    }
+
+    fn hold_send_keys(
+        &self,
+        element: &FakeElement,
+        holdkeys: &str,
+        keys: &str,
+        _interval_ms: u64,
+    ) -> Result<(), BackendError> {
+        self.record_keys(*element, &format!("{holdkeys}{keys}"));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{type_into, FakeBackend};
+    use crate::{AbcError, AbcSession, Backend, CustomerScreen, InventoryScreen, Timing};
+
+    #[test]
+    fn test_type_into() {
+        let mut value = "OLD".to_string();
+        type_into(&mut value, "{Delete}NEW {{}1{}}{Enter}");
+        assert_eq!(value, "NEW {1}");
+    }
+
+    #[test]
+    fn test_inventory_workflow() {
+        let fake = FakeBackend::new();
+        fake.add_screen("ABC Accounting Client - Store 1");
+        fake.on_keys("{F10}I", |fake, _, _| {
+            let screen = fake.add_screen("Inventory - Items (I)");
+            fake.add_text_boxes(screen, &[""; 44]);
+        });
+        fake.on_keys("WIDGET{Enter}", |fake, _, keys| {
+            // Only loading the item fills in its description, not typing one
+            if keys == "WIDGET{Enter}" {
+                let screen = fake.find_screen("Inventory - Items (I)").unwrap();
+                fake.set_text_box(screen, 1, "BLUE WIDGET");
+            }
+        });
+
+        let session = AbcSession::new(fake);
+        let inventory_window = session.load_inventory_screen().unwrap();
+        session.load_item(&inventory_window, "WIDGET").unwrap();
+        assert_eq!(session.get_desc(&inventory_window).unwrap(), "BLUE WIDGET");
+
+        session.set_desc(&inventory_window, "RED WIDGET").unwrap();
+        assert_eq!(session.get_desc(&inventory_window).unwrap(), "RED WIDGET");
+    }
+
+    #[test]
+    fn test_wrong_screen() {
+        let fake = FakeBackend::new();
+        let customer_screen = fake.add_screen("Sales - Customers (C)");
+
+        match InventoryScreen::new(&fake, customer_screen) {
+            Err(AbcError::WrongScreen { expected, actual }) => {
+                assert_eq!(expected, "Inventory - Items (I)");
+                assert_eq!(actual, "Sales - Customers (C)");
+            }
+            other => panic!("expected WrongScreen, got {other:?}"),
+        }
+        assert!(CustomerScreen::new(&fake, customer_screen).is_ok());
+    }
+
+    #[test]
+    fn test_save_changes_popup() {
+        let fake = FakeBackend::new();
+        let abc_window = fake.add_screen("ABC Accounting Client");
+        fake.on_keys("{Ctrl}N", |fake, _, _| {
+            fake.add_popup("Save changes before proceeding?");
+        });
+
+        let session = AbcSession::with_window(fake, abc_window);
+        session.send_ctrl_n(false).unwrap();
+        let fake = session.backend();
+        let (popup, keys) = fake.sent_keys().pop().unwrap();
+        assert_eq!(keys, "{right}{enter}");
+        assert_eq!(
+            fake.find_popup(None, "Save changes", 0).unwrap(),
+            None,
+            "popup {popup:?} should be dismissed"
+        );
+    }
+
+    #[test]
+    fn test_return_to_main_menu() {
+        let fake = FakeBackend::new();
+        let abc_window = fake.add_screen("ABC Accounting Client");
+        fake.add_screen("Utilities - Special User Reports");
+        fake.add_screen("Inventory - Reports");
+        fake.add_screen("Inventory - Items (I)");
+        fake.add_popup("Information");
+        fake.on_keys("{Ctrl}N", |fake, _, _| {
+            fake.add_popup("Save changes before proceeding?");
+        });
+        fake.on_keys("{Esc}", |fake, _, _| {
+            let open = [
+                "Inventory - Items (I)",
+                "Inventory - Reports",
+                "Utilities - Special User Reports",
+            ]
+            .iter()
+            .find_map(|title| fake.find_screen(title).ok());
+            if let Some(screen) = open {
+                fake.close(screen);
+            }
+        });
+
+        let session = AbcSession::with_window(fake, abc_window)
+            .with_timing(Timing::default().with_scale(0.1));
+        session.return_to_main_menu(true).unwrap();
+        let fake = session.backend();
+        assert!(fake.find_screen("Inventory - Items (I)").is_err());
+        assert!(fake.find_screen("Inventory - Reports").is_err());
+        assert!(fake
+            .find_screen("Utilities - Special User Reports")
+            .is_err());
+        let keys: Vec<_> = fake.sent_keys().into_iter().map(|(_, keys)| keys).collect();
+        assert!(keys.contains(&"{right}{enter}".to_string()));
+    }
+}
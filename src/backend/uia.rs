@@ -0,0 +1,244 @@
+use std::time::{Duration, Instant};
+
+use uiautomation::errors::ERR_NOTFOUND;
+use uiautomation::{types::UIProperty, UIAutomation, UIElement, UIMatcher};
+
+use super::{Backend, BackendError, Bounds};
+use crate::{wait, SHORT_WAIT_MS};
+
+impl From<uiautomation::Error> for BackendError {
+    fn from(value: uiautomation::Error) -> Self {
+        match value.code() {
+            ERR_NOTFOUND => BackendError::not_found(value.to_string()),
+            _ => BackendError::other(value.to_string()),
+        }
+    }
+}
+
+/// [`Backend`] that drives a live Client4 instance through Windows UI Automation
+pub struct UiaBackend {
+    automation: UIAutomation,
+    root: UIElement,
+    timeout_ms: u64,
+    process_id: Option<u32>,
+}
+
+impl UiaBackend {
+    /// Create a new instance of the automation controller
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(BackendError)` if UI Automation cannot be initialized
+    pub fn new() -> Result<Self, BackendError> {
+        let automation = UIAutomation::new()?;
+        let root = automation.get_root_element()?;
+        Ok(UiaBackend {
+            automation,
+            root,
+            timeout_ms: SHORT_WAIT_MS * 30,
+            process_id: None,
+        })
+    }
+
+    /// The underlying `UIAutomation` instance, for anything the [`Backend`] trait does not cover
+    pub fn automation(&self) -> &UIAutomation {
+        &self.automation
+    }
+
+    /// Create a matcher that searches from the desktop. See [`Backend::set_timeout`]
+    fn matcher(&self) -> UIMatcher {
+        self.automation
+            .create_matcher()
+            .from(self.root.clone())
+            .timeout(self.timeout_ms)
+    }
+
+    /// Find the first element matched by the matcher `matcher` creates, waiting at most
+    /// `timeout_ms`. Elements outside of the process the backend is scoped to are skipped. See
+    /// [`Backend::scope_to_process`]
+    fn find_first(
+        &self,
+        matcher: impl Fn() -> UIMatcher,
+        timeout_ms: u64,
+    ) -> uiautomation::Result<UIElement> {
+        let Some(process_id) = self.process_id else {
+            return matcher().timeout(timeout_ms).find_first();
+        };
+        let start = Instant::now();
+        loop {
+            for element in matcher().timeout(0).find_all().unwrap_or_default() {
+                if element.get_process_id()? as u32 == process_id {
+                    return Ok(element);
+                }
+            }
+            if start.elapsed() >= Duration::from_millis(timeout_ms) {
+                return Err(uiautomation::Error::new(
+                    ERR_NOTFOUND,
+                    &format!("no matching element in process {process_id}"),
+                ));
+            }
+            wait(SHORT_WAIT_MS);
+        }
+    }
+}
+
+impl Backend for UiaBackend {
+    type Element = UIElement;
+
+    fn find_screen(&self, name: &str) -> Result<UIElement, BackendError> {
+        Ok(self.find_first(|| self.matcher().contains_name(name), self.timeout_ms)?)
+    }
+
+    fn screen_exists(&self, name: &str) -> Result<bool, BackendError> {
+        Ok(self
+            .find_first(|| self.matcher().contains_name(name), 0)
+            .is_ok())
+    }
+
+    fn find_screen_named(&self, name: &str) -> Result<UIElement, BackendError> {
+        Ok(self.find_first(|| self.matcher().name(name), self.timeout_ms)?)
+    }
+
+    fn screen_named_exists(&self, name: &str) -> Result<bool, BackendError> {
+        Ok(self.find_first(|| self.matcher().name(name), 0).is_ok())
+    }
+
+    fn find_windows(&self, name: &str) -> Result<Vec<UIElement>, BackendError> {
+        match self.matcher().timeout(0).contains_name(name).find_all() {
+            Err(e) if e.code() == ERR_NOTFOUND => Ok(Vec::new()),
+            found => Ok(found?),
+        }
+    }
+
+    fn process_id(&self, element: &UIElement) -> Result<u32, BackendError> {
+        Ok(element.get_process_id()? as u32)
+    }
+
+    fn scope_to_process(&mut self, process_id: Option<u32>) {
+        self.process_id = process_id;
+    }
+
+    fn find_popup(
+        &self,
+        from: Option<&UIElement>,
+        title: &str,
+        timeout_ms: u64,
+    ) -> Result<Option<UIElement>, BackendError> {
+        let from = match from {
+            Some(element) => element.to_owned(),
+            None => self.root.clone(),
+        };
+        let matcher = || {
+            self.automation
+                .create_matcher()
+                .from(from.clone())
+                .classname("ThunderRT6FormDC")
+                .contains_name(title)
+        };
+        Ok(self.find_first(matcher, timeout_ms).ok())
+    }
+
+    fn text_boxes(&self, screen: &UIElement) -> Result<Vec<UIElement>, BackendError> {
+        Ok(self
+            .matcher()
+            .from(screen.to_owned())
+            .classname("ThunderRT6TextBox")
+            .find_all()?)
+    }
+
+    fn text_boxes_within(
+        &self,
+        screen: &UIElement,
+        timeout_ms: u64,
+    ) -> Result<Vec<UIElement>, BackendError> {
+        Ok(self
+            .matcher()
+            .from(screen.to_owned())
+            .timeout(timeout_ms)
+            .classname("ThunderRT6TextBox")
+            .find_all()?)
+    }
+
+    fn descendants(
+        &self,
+        element: &UIElement,
+        classname: &str,
+    ) -> Result<Vec<UIElement>, BackendError> {
+        let found = self
+            .matcher()
+            .from(element.to_owned())
+            .timeout(0)
+            .classname(classname)
+            .find_all();
+        match found {
+            Err(e) if e.code() == ERR_NOTFOUND => Ok(Vec::new()),
+            found => Ok(found?),
+        }
+    }
+
+    fn children(&self, element: &UIElement) -> Result<Vec<UIElement>, BackendError> {
+        let walker = self.automation.get_control_view_walker()?;
+        let mut children = Vec::new();
+        let mut next = walker.get_first_child(element).ok();
+        while let Some(child) = next {
+            next = walker.get_next_sibling(&child).ok();
+            children.push(child);
+        }
+        Ok(children)
+    }
+
+    fn classname(&self, element: &UIElement) -> Result<String, BackendError> {
+        Ok(element.get_classname()?)
+    }
+
+    fn control_type(&self, element: &UIElement) -> Result<String, BackendError> {
+        Ok(format!("{:?}", element.get_control_type()?))
+    }
+
+    fn bounds(&self, element: &UIElement) -> Result<Bounds, BackendError> {
+        let rect = element.get_bounding_rectangle()?;
+        Ok(Bounds {
+            left: rect.get_left(),
+            top: rect.get_top(),
+            right: rect.get_right(),
+            bottom: rect.get_bottom(),
+        })
+    }
+
+    fn name(&self, element: &UIElement) -> Result<String, BackendError> {
+        Ok(element.get_name()?)
+    }
+
+    fn value(&self, element: &UIElement) -> Result<String, BackendError> {
+        Ok(element
+            .get_property_value(UIProperty::ValueValue)?
+            .get_string()?)
+    }
+
+    fn click(&self, element: &UIElement) -> Result<(), BackendError> {
+        Ok(element.click()?)
+    }
+
+    fn set_timeout(&mut self, timeout_ms: u64) {
+        self.timeout_ms = timeout_ms;
+    }
+
+    fn send_keys(
+        &self,
+        element: &UIElement,
+        keys: &str,
+        interval_ms: u64,
+    ) -> Result<(), BackendError> {
+        Ok(element.send_keys(keys, interval_ms)?)
+    }
+
+    fn hold_send_keys(
+        &self,
+        element: &UIElement,
+        holdkeys: &str,
+        keys: &str,
+        interval_ms: u64,
+    ) -> Result<(), BackendError> {
+        Ok(element.hold_send_keys(holdkeys, keys, interval_ms)?)
+    }
+}
@@ -0,0 +1,326 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use zeroize::Zeroizing;
+
+use crate::{AbcError, Result};
+
+/// Environment variable [`EnvCredentials::new`] reads the username from
+pub const USERNAME_VAR: &str = "ABC_USERNAME";
+/// Environment variable [`EnvCredentials::new`] reads the password from
+pub const PASSWORD_VAR: &str = "ABC_PASSWORD";
+
+/// Marks the start of a file written by [`EncryptedFileCredentials::write`]
+const ENCRYPTED_MAGIC: &[u8] = b"ABCCRED1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// A password that is wiped from memory when it is dropped, and that prints as `***` so it
+/// cannot end up in logs or error messages by accident
+#[derive(Clone)]
+pub struct Password(Zeroizing<String>);
+
+impl Password {
+    /// Take ownership of `password`
+    pub fn new(password: impl Into<String>) -> Self {
+        Password(Zeroizing::new(password.into()))
+    }
+
+    /// The plaintext password. Do not hold on to copies of it
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Password(***)")
+    }
+}
+
+/// A username and password to log into Client4 with
+#[derive(Debug, Clone)]
+pub struct LoginCredentials {
+    pub username: String,
+    pub password: Password,
+}
+
+impl LoginCredentials {
+    /// Pair `username` with `password`
+    pub fn new(username: &str, password: Password) -> Self {
+        LoginCredentials {
+            username: username.to_string(),
+            password,
+        }
+    }
+
+    /// Read a username from the first line of `contents` and a password from the second
+    fn parse(contents: &str, source: &Path) -> Result<Self> {
+        let mut lines = contents.lines();
+        let username = lines.next().unwrap_or_default().trim();
+        let password = lines.next().unwrap_or_default();
+        if username.is_empty() || password.is_empty() {
+            return Err(AbcError::Credentials(format!(
+                "{} must hold the username on the first line and the password on the second",
+                source.display()
+            )));
+        }
+        Ok(LoginCredentials::new(username, Password::new(password)))
+    }
+}
+
+/// Somewhere to get the Client4 username and password from, so they do not have to be written
+/// into scripts. See [`AbcSession::login_with`](crate::AbcSession::login_with)
+pub trait Credentials {
+    /// Look up the username and password
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Credentials)` if they cannot be found. The error never contains the
+    /// password
+    fn credentials(&self) -> Result<LoginCredentials>;
+}
+
+impl Credentials for LoginCredentials {
+    fn credentials(&self) -> Result<LoginCredentials> {
+        Ok(self.clone())
+    }
+}
+
+impl<C: Credentials + ?Sized> Credentials for &C {
+    fn credentials(&self) -> Result<LoginCredentials> {
+        (**self).credentials()
+    }
+}
+
+/// Credentials read from environment variables
+#[derive(Debug, Clone)]
+pub struct EnvCredentials {
+    username_var: String,
+    password_var: String,
+}
+
+impl EnvCredentials {
+    /// Read the credentials from [`USERNAME_VAR`] and [`PASSWORD_VAR`]
+    pub fn new() -> Self {
+        EnvCredentials::with_vars(USERNAME_VAR, PASSWORD_VAR)
+    }
+
+    /// Read the credentials from `username_var` and `password_var`
+    pub fn with_vars(username_var: &str, password_var: &str) -> Self {
+        EnvCredentials {
+            username_var: username_var.to_string(),
+            password_var: password_var.to_string(),
+        }
+    }
+}
+
+impl Default for EnvCredentials {
+    fn default() -> Self {
+        EnvCredentials::new()
+    }
+}
+
+/// Read the environment variable `var`. The error deliberately leaves out the value, since a
+/// non-unicode password would otherwise end up in it
+fn env_var(var: &str) -> Result<String> {
+    std::env::var(var).map_err(|_| {
+        AbcError::Credentials(format!(
+            "environment variable {var} is not set or not unicode"
+        ))
+    })
+}
+
+impl Credentials for EnvCredentials {
+    fn credentials(&self) -> Result<LoginCredentials> {
+        let username = env_var(&self.username_var)?;
+        let password = Password::new(env_var(&self.password_var)?);
+        Ok(LoginCredentials::new(&username, password))
+    }
+}
+
+/// Credentials read from a plain text file with the username on the first line and the password
+/// on the second. The file must only be readable by the current user
+#[derive(Debug, Clone)]
+pub struct FileCredentials {
+    path: PathBuf,
+}
+
+impl FileCredentials {
+    /// Read the credentials from the file at `path`
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        FileCredentials {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl Credentials for FileCredentials {
+    fn credentials(&self) -> Result<LoginCredentials> {
+        check_permissions(&self.path)?;
+        let contents =
+            Zeroizing::new(fs::read_to_string(&self.path).map_err(io_error(&self.path))?);
+        LoginCredentials::parse(&contents, &self.path)
+    }
+}
+
+/// Turn an error reading `path` into `AbcError::Credentials`
+fn io_error(path: &Path) -> impl Fn(std::io::Error) -> AbcError + '_ {
+    move |e| AbcError::Credentials(format!("{}: {e}", path.display()))
+}
+
+/// Refuse credential files that other users can read
+#[cfg(unix)]
+fn check_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(path)
+        .map_err(io_error(path))?
+        .permissions()
+        .mode();
+    if mode & 0o077 != 0 {
+        return Err(AbcError::Credentials(format!(
+            "{} can be read by other users. Restrict it with `chmod 600`",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Refuse credential files that other users can read. Reading ACLs is out of reach of the
+/// standard library, so this settles for requiring the file to be inside the user's profile,
+/// which only the user and administrators can read by default
+#[cfg(windows)]
+fn check_permissions(path: &Path) -> Result<()> {
+    let profile = std::env::var_os("USERPROFILE")
+        .and_then(|profile| fs::canonicalize(profile).ok())
+        .ok_or_else(|| AbcError::Credentials("USERPROFILE is not set".to_string()))?;
+    let path = fs::canonicalize(path).map_err(io_error(path))?;
+    if !path.starts_with(&profile) {
+        return Err(AbcError::Credentials(format!(
+            "{} must be inside {} so other users cannot read it",
+            path.display(),
+            profile.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn check_permissions(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Credentials read from a file encrypted with ChaCha20-Poly1305, using a key derived from a
+/// passphrase with Argon2. Create the file with [`EncryptedFileCredentials::write`]
+#[derive(Debug, Clone)]
+pub struct EncryptedFileCredentials {
+    path: PathBuf,
+    passphrase: Password,
+}
+
+impl EncryptedFileCredentials {
+    /// Decrypt the credentials in the file at `path` with `passphrase`
+    pub fn new(path: impl AsRef<Path>, passphrase: Password) -> Self {
+        EncryptedFileCredentials {
+            path: path.as_ref().to_path_buf(),
+            passphrase,
+        }
+    }
+
+    /// Encrypt `credentials` with `passphrase` and write them to `path`, replacing the file if
+    /// it exists
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Credentials)` if the file cannot be written
+    pub fn write(
+        path: impl AsRef<Path>,
+        credentials: &LoginCredentials,
+        passphrase: &Password,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let mut plaintext = Zeroizing::new(credentials.username.clone());
+        plaintext.push('\n');
+        plaintext.push_str(credentials.password.expose());
+        let ciphertext = cipher(passphrase, &salt)?
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| AbcError::Credentials("could not encrypt the credentials".to_string()))?;
+
+        let contents = [ENCRYPTED_MAGIC, &salt, &nonce, &ciphertext].concat();
+        fs::write(path, contents).map_err(io_error(path))
+    }
+}
+
+/// Derive the key for `passphrase` and `salt`
+fn cipher(passphrase: &Password, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = Zeroizing::new([0; 32]);
+    Argon2::default()
+        .hash_password_into(passphrase.expose().as_bytes(), salt, key.as_mut())
+        .map_err(|e| AbcError::Credentials(format!("could not derive the key: {e}")))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key.as_ref())))
+}
+
+impl Credentials for EncryptedFileCredentials {
+    fn credentials(&self) -> Result<LoginCredentials> {
+        let contents = fs::read(&self.path).map_err(io_error(&self.path))?;
+        let header_len = ENCRYPTED_MAGIC.len() + SALT_LEN + NONCE_LEN;
+        if contents.len() <= header_len || !contents.starts_with(ENCRYPTED_MAGIC) {
+            return Err(AbcError::Credentials(format!(
+                "{} is not an encrypted credentials file",
+                self.path.display()
+            )));
+        }
+        let (salt, rest) = contents[ENCRYPTED_MAGIC.len()..].split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let plaintext = cipher(&self.passphrase, salt)?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map(Zeroizing::new)
+            .map_err(|_| {
+                AbcError::Credentials(format!(
+                    "could not decrypt {}. The passphrase is wrong or the file is damaged",
+                    self.path.display()
+                ))
+            })?;
+        let plaintext = std::str::from_utf8(&plaintext).map_err(|_| {
+            AbcError::Credentials(format!("{} does not decrypt to text", self.path.display()))
+        })?;
+        LoginCredentials::parse(plaintext, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Credentials, EncryptedFileCredentials, LoginCredentials, Password};
+
+    #[test]
+    fn test_encrypted_file_credentials() {
+        let path = std::env::temp_dir().join(format!("abc-credentials-{}", std::process::id()));
+        let credentials = LoginCredentials::new("clerk", Password::new("hunter2"));
+        assert!(!format!("{credentials:?}").contains("hunter2"));
+
+        EncryptedFileCredentials::write(&path, &credentials, &Password::new("open sesame"))
+            .unwrap();
+        assert!(!std::fs::read(&path)
+            .unwrap()
+            .windows(7)
+            .any(|w| w == b"hunter2"));
+        let decrypted = EncryptedFileCredentials::new(&path, Password::new("open sesame"))
+            .credentials()
+            .unwrap();
+        assert_eq!(decrypted.username, "clerk");
+        assert_eq!(decrypted.password.expose(), "hunter2");
+
+        let wrong = EncryptedFileCredentials::new(&path, Password::new("guess")).credentials();
+        std::fs::remove_file(&path).unwrap();
+        assert!(!wrong.unwrap_err().to_string().contains("hunter2"));
+    }
+}
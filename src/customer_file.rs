@@ -1,8 +1,13 @@
-use crate::{create_matcher_wrapper, UIAutomation, UIElement, SHORT_WAIT_MS};
-use uiautomation::types::UIProperty;
+use tracing::debug_span;
 
-/// Control the ABC Client4 window to load the Customer records screen, and return the `UIElement`
-/// that represents that screen.
+use crate::keys::{Key, Keys};
+use crate::trace::traced;
+use crate::{AbcSession, Backend, CustomerScreen, Result};
+#[cfg(windows)]
+use crate::{UIElement, UiaBackend};
+
+/// Control the ABC Client4 window to load the Customer records screen, and return the
+/// [`CustomerScreen`] that represents that screen.
 ///
 /// # Arguments
 ///
@@ -11,31 +16,19 @@ use uiautomation::types::UIProperty;
 ///
 /// # Returns
 ///
-/// If successful, return the `UIElement` that represents the Customer screen. This is a control
-/// inside Client4 whose name contains "Sales - Customers (C)"
+/// If successful, return the [`CustomerScreen`] that represents the Customer screen. This is a
+/// control inside Client4 whose name contains "Sales - Customers (C)"
 ///
 /// If any key combos fail to send or if the customer screen cannot be found, return
-/// `Err(uiautomation::Error)`
+/// `Err(AbcError)`
 ///
 /// # Errors
 ///
 /// If any key combos fail to send or if the customer screen cannot be found, return
-/// `Err(uiautomation::Error)`
-pub fn load_customer_screen(abc_window: &UIElement) -> uiautomation::Result<UIElement> {
-    let automation = UIAutomation::new()?;
-
-    if let Ok(customer_screen) = create_matcher_wrapper(&automation)?
-        .contains_name("Sales - Customers (C)")
-        .find_first()
-    {
-        return Ok(customer_screen);
-    }
-
-    abc_window.send_keys("{F10}C", SHORT_WAIT_MS * 3)?;
-
-    create_matcher_wrapper(&automation)?
-        .contains_name("Sales - Customers (C)")
-        .find_first()
+/// `Err(AbcError)`
+#[cfg(windows)]
+pub fn load_customer_screen(abc_window: &UIElement) -> Result<CustomerScreen> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).load_customer_screen()
 }
 
 /// Get the JDF account ID for a given customer from a running Client4 window.
@@ -44,8 +37,8 @@ pub fn load_customer_screen(abc_window: &UIElement) -> uiautomation::Result<UIEl
 ///
 /// * `customer_screen` - Reference to the customer window of a Client4 instance. The name of the
 /// element should contain "Sales - Customer (C)". The simplest way to get this value is to call
-/// `abc_controller::load_customer_screen`, which will return the `UIElement` representing the
-/// Customer screen.
+/// `abc_controller::load_customer_screen`, which will return the [`CustomerScreen`] representing
+/// the Customer screen.
 ///
 /// * `customer_code` - The ID of the customer to fetch the JDF code for. Eg. "DOEJO 0"
 ///
@@ -55,68 +48,115 @@ pub fn load_customer_screen(abc_window: &UIElement) -> uiautomation::Result<UIEl
 /// number, return `String::new()`.
 ///
 /// If any key combos fail to send, or if the "ThunderRT6TextBox" containing the JDF account number
-/// cannot be found, return `Err(uiautomation::Error)`
+/// cannot be found, return `Err(AbcError)`
 ///
 /// # Errors
 ///
-/// Return `Err(uiautomation::Error)` if any key combos fail or the text box containing the JDF
+/// Return `Err(AbcError)` if any key combos fail or the text box containing the JDF
 /// account number cannot be found
+#[cfg(windows)]
 pub fn jdf_account_by_customer(
-    customer_screen: &UIElement,
+    customer_screen: &CustomerScreen,
     customer_code: &str,
-) -> uiautomation::Result<String> {
-    let automation = UIAutomation::new()?;
-
-    customer_screen.send_keys(
-        &format!("{{up}}{}{{enter}}", customer_code),
-        SHORT_WAIT_MS / 4,
-    )?;
-
-    // The John Deere Account number is the 29th (counting from 1) ThunderRT6TextBox
-    let text_boxes = automation
-        .create_matcher()
-        .from(customer_screen.to_owned())
-        .timeout(SHORT_WAIT_MS)
-        .classname("ThunderRT6TextBox")
-        .find_all()?;
-    let jdf_account_text_box = match text_boxes.get(28) {
-        Some(b) => b,
-        None => return Ok(String::new()),
-    };
-    let jdf_account_variant = jdf_account_text_box.get_property_value(UIProperty::ValueValue)?;
-    jdf_account_variant.get_string()
+) -> Result<String> {
+    AbcSession::new(UiaBackend::new()?).jdf_account_by_customer(customer_screen, customer_code)
 }
 
 /// Controls the Client4 Customer Screen to load a specific customer file by entering the
-/// customer's id into the appropriate field. 
+/// customer's id into the appropriate field.
 ///
-/// # Arguments 
+/// # Arguments
 ///
-/// * `customer_screen` - The `UIElement` that represents the Customer Screen in the ABC Client4
-/// program
-/// * `customer_code` - The unique identifier for the customer to load 
+/// * `customer_screen` - The [`CustomerScreen`] that represents the Customer Screen in the ABC
+/// Client4 program
+/// * `customer_code` - The unique identifier for the customer to load
 ///
-/// # Returns 
+/// # Returns
 ///
 /// If successful, return unit type. If there is a problem locating the textbox to enter the
-/// customer code into or if there is a problem sending keys, then return `Err(uiautomation::Error)`
+/// customer code into or if there is a problem sending keys, then return `Err(AbcError)`
 ///
-/// # Errors 
+/// # Errors
 ///
-/// Will return `Err(uiautomation::Error)` if there is a problem locating the textbox to enter the
+/// Will return `Err(AbcError)` if there is a problem locating the textbox to enter the
 /// customer code into or if there is a problem sending keys
-pub fn load_customer_record(
-    customer_screen: &UIElement,
-    customer_code: &str,
-) -> uiautomation::Result<()> {
-    let automation = UIAutomation::new()?;
+#[cfg(windows)]
+pub fn load_customer_record(customer_screen: &CustomerScreen, customer_code: &str) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).load_customer_record(customer_screen, customer_code)
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Same as [`load_customer_screen`]
+    pub fn load_customer_screen(&self) -> Result<CustomerScreen<B::Element>> {
+        traced(
+            debug_span!("load_screen", screen = CustomerScreen::<B::Element>::TITLE),
+            || {
+                if let Ok(customer_screen) = CustomerScreen::find(&self.backend) {
+                    return Ok(customer_screen);
+                }
+
+                let abc_window = self.abc_window()?;
+                self.send_keys(
+                    abc_window,
+                    &Keys::new().key(Key::F(10)).text("C"),
+                    self.timing.short() * 3,
+                )?;
+
+                CustomerScreen::find(&self.backend)
+            },
+        )
+    }
+
+    /// Same as [`jdf_account_by_customer`]
+    pub fn jdf_account_by_customer(
+        &self,
+        customer_screen: &CustomerScreen<B::Element>,
+        customer_code: &str,
+    ) -> Result<String> {
+        traced(
+            debug_span!("jdf_account_by_customer", customer_code),
+            || {
+                self.send_keys(
+                    customer_screen.element(),
+                    &Keys::new().key(Key::Up).text(customer_code).key(Key::Enter),
+                    self.timing.short() / 4,
+                )?;
 
-    let customer_code_control = create_matcher_wrapper(&automation)?
-        .classname("ThunderRT6TextBox")
-        .from(customer_screen.to_owned())
-        .find_first()?;
-    customer_code_control.click()?;
-    customer_code_control.send_keys(&format!("{}{{enter}}", customer_code), SHORT_WAIT_MS)?;
+                // By default, the John Deere Account number is the 29th (counting from 1)
+                // ThunderRT6TextBox. Without that text box, there is no account number to read,
+                // so the text boxes are only looked for briefly
+                let text_boxes = self.backend.text_boxes_within(
+                    customer_screen.element(),
+                    self.timing
+                        .delay("jdf_account_lookup", self.timing.short_wait_ms),
+                )?;
+                match text_boxes.get(self.field_maps.customer.index("jdf_account")?) {
+                    Some(text_box) => Ok(self.backend.value(text_box)?),
+                    None => Ok(String::new()),
+                }
+            },
+        )
+    }
 
-    Ok(())
+    /// Same as [`load_customer_record`]
+    pub fn load_customer_record(
+        &self,
+        customer_screen: &CustomerScreen<B::Element>,
+        customer_code: &str,
+    ) -> Result<()> {
+        traced(debug_span!("load_customer_record", customer_code), || {
+            let customer_code_control = self.text_box_at(
+                customer_screen.element(),
+                self.field_maps.customer.index("customer_code")?,
+            )?;
+            self.backend.click(&customer_code_control)?;
+            self.send_keys(
+                &customer_code_control,
+                &Keys::new().text(customer_code).key(Key::Enter),
+                self.timing.short(),
+            )?;
+
+            Ok(())
+        })
+    }
 }
@@ -0,0 +1,194 @@
+use std::fmt;
+
+use crate::{AbcSession, Backend, Bounds, FieldMap, Result};
+#[cfg(windows)]
+use crate::{UIElement, UiaBackend};
+
+/// Class of the labels Client4 puts next to its text boxes
+const LABEL_CLASS: &str = "ThunderRT6Label";
+
+/// A ThunderRT6TextBox found by [`discover_fields`], along with the label closest to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredField {
+    /// Position of the text box among the ThunderRT6TextBoxes of the screen. This is the index a
+    /// [`FieldMap`] refers to
+    pub index: usize,
+    /// Text of the nearest label, without the trailing colon. `None` if the screen has no label
+    /// to the left of or above the text box
+    pub label: Option<String>,
+    /// The current contents of the text box
+    pub value: String,
+    /// Where the text box is on screen
+    pub bounds: Bounds,
+}
+
+impl fmt::Display for DiscoveredField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}",
+            self.index,
+            self.label.as_deref().unwrap_or("?"),
+            self.value
+        )
+    }
+}
+
+/// How far apart a label and a text box are. Labels sit to the left of or above their text box,
+/// so anything to the right or below does not count, and vertical distance is weighed more
+/// heavily than horizontal distance so labels on the same row win
+fn label_distance(label: &Bounds, text_box: &Bounds) -> Option<i64> {
+    if label.left >= text_box.right || label.top >= text_box.bottom {
+        return None;
+    }
+    let dx = (text_box.left - label.right).max(0) as i64;
+    let dy = (text_box.top - label.bottom).max(0) as i64;
+    Some(dx * dx + 4 * dy * dy)
+}
+
+/// Turn label text such as "&Vendor:" into "Vendor"
+fn clean_label(text: &str) -> String {
+    text.replace('&', "")
+        .trim()
+        .trim_end_matches(':')
+        .trim()
+        .to_string()
+}
+
+/// Turn label text such as "Sale G/L" into a field name such as "sale_g_l"
+fn field_name(label: &str) -> String {
+    let mut name = String::new();
+    for c in label.chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    name.trim_end_matches('_').to_string()
+}
+
+/// Build a draft [`FieldMap`] out of the fields found by [`discover_fields`]. Each field is named
+/// after its label in snake case, eg. "Sale G/L" becomes `sale_g_l`. Fields without a label are
+/// called `field_<index>`, and repeated names get the index appended. The result is only a
+/// starting point and should be checked against the screen before it is used
+pub fn draft_field_map(fields: &[DiscoveredField]) -> FieldMap {
+    let mut map = FieldMap::new();
+    for field in fields {
+        let name = field
+            .label
+            .as_deref()
+            .map(field_name)
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| format!("field_{}", field.index));
+        match map.get(&name) {
+            Some(_) => map.set(&format!("{name}_{}", field.index), field.index),
+            None => map.set(&name, field.index),
+        }
+    }
+    map
+}
+
+/// List every ThunderRT6TextBox on a Client4 screen with the label closest to it and its current
+/// value, to work out the [`FieldMap`] of a screen without counting text boxes by hand. See
+/// [`draft_field_map`]
+///
+/// # Arguments
+///
+/// * `screen` - The `UIElement` of the Client4 screen to look for text boxes on
+///
+/// # Errors
+///
+/// Will return [`AbcError::Automation`](crate::AbcError::Automation) if the text boxes or labels
+/// cannot be read
+#[cfg(windows)]
+pub fn discover_fields(screen: &UIElement) -> Result<Vec<DiscoveredField>> {
+    AbcSession::new(UiaBackend::new()?).discover_fields(screen)
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Same as [`discover_fields`]
+    pub fn discover_fields(&self, screen: &B::Element) -> Result<Vec<DiscoveredField>> {
+        let mut labels = Vec::new();
+        for label in self.backend.descendants(screen, LABEL_CLASS)? {
+            let text = clean_label(&self.backend.name(&label)?);
+            if !text.is_empty() {
+                labels.push((text, self.backend.bounds(&label)?));
+            }
+        }
+
+        let mut fields = Vec::new();
+        for (index, text_box) in self.backend.text_boxes(screen)?.iter().enumerate() {
+            let bounds = self.backend.bounds(text_box)?;
+            let label = labels
+                .iter()
+                .filter_map(|(text, label)| Some((label_distance(label, &bounds)?, text)))
+                .min_by_key(|(distance, _)| *distance)
+                .map(|(_, text)| text.clone());
+            fields.push(DiscoveredField {
+                index,
+                label,
+                value: self.backend.value(text_box)?,
+                bounds,
+            });
+        }
+        Ok(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::draft_field_map;
+    use crate::backend::FakeBackend;
+    use crate::{AbcSession, Bounds};
+
+    fn bounds(left: i32, top: i32, right: i32, bottom: i32) -> Bounds {
+        Bounds {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    #[test]
+    fn test_discover_fields() {
+        let fake = FakeBackend::new();
+        let screen = fake.add_screen("Sales - Customers (C)");
+        let labels = [
+            ("C&ustomer:", bounds(10, 10, 80, 30)),
+            ("JDF Account", bounds(200, 10, 280, 30)),
+            ("Notes", bounds(10, 50, 80, 70)),
+        ];
+        for (text, at) in labels {
+            let label = fake.add_element(screen, "ThunderRT6Label", text, "");
+            fake.set_bounds(label, at);
+        }
+        let text_boxes = fake.add_text_boxes(screen, &["ACME", "12345", "", "CALL FIRST"]);
+        fake.set_bounds(text_boxes[0], bounds(90, 10, 190, 30));
+        fake.set_bounds(text_boxes[1], bounds(290, 10, 390, 30));
+        fake.set_bounds(text_boxes[2], bounds(5, 0, 8, 5));
+        fake.set_bounds(text_boxes[3], bounds(10, 75, 390, 120));
+
+        let session = AbcSession::new(fake);
+        let fields = session.discover_fields(&screen).unwrap();
+        let found: Vec<_> = fields
+            .iter()
+            .map(|f| (f.index, f.label.as_deref(), f.value.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (0, Some("Customer"), "ACME"),
+                (1, Some("JDF Account"), "12345"),
+                (2, None, ""),
+                (3, Some("Notes"), "CALL FIRST"),
+            ]
+        );
+
+        let map = draft_field_map(&fields);
+        assert_eq!(map.get("jdf_account"), Some(1));
+        assert_eq!(map.get("field_2"), Some(2));
+        assert_eq!(map.get("notes"), Some(3));
+    }
+}
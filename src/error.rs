@@ -0,0 +1,112 @@
+use std::fmt;
+
+use crate::backend::BackendError;
+use crate::popups::PopupInfo;
+
+/// Convenience alias for results returned throughout this crate
+pub type Result<T, E = AbcError> = std::result::Result<T, E>;
+
+/// Everything that can go wrong while driving Client4
+#[derive(Debug)]
+pub enum AbcError {
+    /// The element handed to an operation, or the screen Client4 opened, is not the one the
+    /// operation works on
+    WrongScreen { expected: String, actual: String },
+
+    /// A screen does not have a ThunderRT6TextBox at the requested index
+    FieldNotFound { screen: String, index: usize },
+
+    /// A value was entered into a field, but reading the field back returned something else
+    VerificationFailed {
+        field: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// A record the operation would create, such as an item, is already in Client4
+    AlreadyExists { record: String },
+
+    /// Client4 raised a dialog the operation did not expect. `text` explains what it means for
+    /// the operation
+    UnexpectedPopup { popup: PopupInfo, text: String },
+
+    /// The operation requires a logged in Client4 user
+    NotLoggedIn,
+
+    /// Client4 did not reach the expected state in time
+    Timeout { waiting_for: String },
+
+    /// A value read from Client4 could not be parsed
+    Parse { field: String, value: String },
+
+    /// An argument passed to the operation is out of range
+    InvalidArgument(String),
+
+    /// A configuration file could not be read or parsed
+    Config(String),
+
+    /// Login credentials could not be loaded. The message never contains the password
+    Credentials(String),
+
+    /// Client4 could not be started
+    Launch(String),
+
+    /// The underlying UI Automation call failed
+    Automation(BackendError),
+}
+
+impl fmt::Display for AbcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbcError::WrongScreen { expected, actual } => {
+                write!(f, "expected the '{expected}' screen, but found '{actual}'")
+            }
+            AbcError::FieldNotFound { screen, index } => {
+                write!(f, "no textbox found at index {index} on '{screen}'")
+            }
+            AbcError::VerificationFailed {
+                field,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "could not verify that {field} was entered correctly: expected '{expected}', \
+                 but found '{actual}'"
+            ),
+            AbcError::AlreadyExists { record } => write!(f, "'{record}' already exists"),
+            AbcError::UnexpectedPopup { popup, text } => {
+                write!(f, "encountered unexpected popup {popup}: {text}")
+            }
+            AbcError::NotLoggedIn => write!(f, "no user is logged into Client4"),
+            AbcError::Timeout { waiting_for } => write!(f, "timed out waiting for {waiting_for}"),
+            AbcError::Parse { field, value } => write!(f, "could not parse {field} from '{value}'"),
+            AbcError::InvalidArgument(message) => write!(f, "invalid argument: {message}"),
+            AbcError::Config(message) => write!(f, "invalid configuration: {message}"),
+            AbcError::Credentials(message) => write!(f, "invalid credentials: {message}"),
+            AbcError::Launch(message) => write!(f, "could not launch Client4: {message}"),
+            AbcError::Automation(e) => write!(f, "ui automation error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for AbcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AbcError::Automation(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<BackendError> for AbcError {
+    fn from(value: BackendError) -> Self {
+        AbcError::Automation(value)
+    }
+}
+
+#[cfg(windows)]
+impl From<uiautomation::Error> for AbcError {
+    fn from(value: uiautomation::Error) -> Self {
+        AbcError::Automation(value.into())
+    }
+}
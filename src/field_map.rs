@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AbcError, CustomerScreen, InventoryScreen, InvoicesScreen, LoginScreen, ReportOptionsScreen,
+    Result, SpecialReportsScreen,
+};
+
+/// Named ThunderRT6TextBox indices for a single Client4 screen. Client4 does not name its text
+/// boxes, so fields are addressed by their zero based position among the ThunderRT6TextBoxes of
+/// the screen
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FieldMap {
+    fields: BTreeMap<String, usize>,
+}
+
+impl FieldMap {
+    /// Create a field map with no fields in it
+    pub fn new() -> Self {
+        FieldMap::default()
+    }
+
+    /// Add or replace a field, returning the updated map
+    pub fn with(mut self, name: &str, index: usize) -> Self {
+        self.set(name, index);
+        self
+    }
+
+    /// Add or replace a field
+    pub fn set(&mut self, name: &str, index: usize) {
+        self.fields.insert(name.to_string(), index);
+    }
+
+    /// Look up the index of a field, if it is mapped
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.fields.get(name).copied()
+    }
+
+    /// Look up the index of a field
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::InvalidArgument)` if there is no field called `name`
+    pub fn index(&self, name: &str) -> Result<usize> {
+        self.get(name).ok_or_else(|| {
+            AbcError::InvalidArgument(format!("no field named '{name}' in the field map"))
+        })
+    }
+
+    /// Look up the name of the field at `index`, if it is mapped
+    pub fn name_of(&self, index: usize) -> Option<&str> {
+        self.iter().find(|(_, i)| *i == index).map(|(name, _)| name)
+    }
+
+    /// Iterate over every `(name, index)` pair in the map, ordered by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.fields
+            .iter()
+            .map(|(name, index)| (name.as_str(), *index))
+    }
+
+    /// Default fields of the "Inventory - Items (I)" screen
+    pub fn inventory() -> Self {
+        FieldMap::new()
+            .with("sku", 0)
+            .with("desc", 1)
+            .with("vendor", 14)
+            .with("weight", 15)
+            .with("list", 25)
+            .with("cost", 26)
+            .with("alt_sku_0", 35)
+            .with("alt_sku_1", 36)
+            .with("alt_sku_2", 37)
+            .with("upc", 38)
+            .with("group", 39)
+            .with("sale_gl", 43)
+    }
+
+    /// Default fields of the "Sales - Customers (C)" screen
+    pub fn customer() -> Self {
+        FieldMap::new()
+            .with("customer_code", 0)
+            .with("jdf_account", 28)
+    }
+
+    /// Default fields of the "Sales - Invoices (R)" screen
+    pub fn invoices() -> Self {
+        FieldMap::new()
+            .with("invoice_number", 0)
+            .with("paid", 29)
+            .with("total", 38)
+    }
+
+    /// Default fields of the "Utilities - System Date and Time" screen
+    pub fn login() -> Self {
+        FieldMap::new().with("password", 0).with("username", 2)
+    }
+
+    /// Default fields of the "Utilities - Special User Reports" screen
+    pub fn special_reports() -> Self {
+        FieldMap::new().with("file", 0).with("report", 1)
+    }
+
+    /// Default fields of the report options screen that follows the special user reports screen
+    pub fn report_options() -> Self {
+        FieldMap::new().with("output", 10)
+    }
+}
+
+/// The [`FieldMap`] of every screen the crate drives
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldMaps {
+    pub inventory: FieldMap,
+    pub customer: FieldMap,
+    pub invoices: FieldMap,
+    pub login: FieldMap,
+    pub special_reports: FieldMap,
+    pub report_options: FieldMap,
+}
+
+impl Default for FieldMaps {
+    fn default() -> Self {
+        FieldMaps {
+            inventory: FieldMap::inventory(),
+            customer: FieldMap::customer(),
+            invoices: FieldMap::invoices(),
+            login: FieldMap::login(),
+            special_reports: FieldMap::special_reports(),
+            report_options: FieldMap::report_options(),
+        }
+    }
+}
+
+impl FieldMaps {
+    /// The field map of the screen whose title is `title`, if the crate drives that screen
+    pub fn for_screen(&self, title: &str) -> Option<&FieldMap> {
+        let maps = [
+            (<InventoryScreen>::TITLE, &self.inventory),
+            (<CustomerScreen>::TITLE, &self.customer),
+            (<InvoicesScreen>::TITLE, &self.invoices),
+            (<LoginScreen>::TITLE, &self.login),
+            (<SpecialReportsScreen>::TITLE, &self.special_reports),
+            (<ReportOptionsScreen>::TITLE, &self.report_options),
+        ];
+        maps.into_iter()
+            .find(|(screen, _)| title.contains(screen))
+            .map(|(_, map)| map)
+    }
+
+    /// Apply overrides on top of these field maps. Screens and fields missing from `overrides`
+    /// keep their current index
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::InvalidArgument)` if `overrides` names a screen that does not exist
+    pub fn apply(&mut self, overrides: BTreeMap<String, FieldMap>) -> Result<()> {
+        for (screen, fields) in overrides {
+            let map = match screen.as_str() {
+                "inventory" => &mut self.inventory,
+                "customer" => &mut self.customer,
+                "invoices" => &mut self.invoices,
+                "login" => &mut self.login,
+                "special_reports" => &mut self.special_reports,
+                "report_options" => &mut self.report_options,
+                _ => {
+                    return Err(AbcError::InvalidArgument(format!(
+                        "unknown screen '{screen}' in field map"
+                    )))
+                }
+            };
+            map.fields.extend(fields.fields);
+        }
+        Ok(())
+    }
+
+    /// Parse overrides in TOML format and apply them on top of the defaults. Each table is a
+    /// screen, and each key a field:
+    ///
+    /// ```toml
+    /// [inventory]
+    /// upc = 40
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Config)` if `toml` cannot be parsed
+    pub fn from_toml_str(toml: &str) -> Result<Self> {
+        let overrides = toml::from_str(toml).map_err(|e| AbcError::Config(e.to_string()))?;
+        let mut maps = FieldMaps::default();
+        maps.apply(overrides)?;
+        Ok(maps)
+    }
+
+    /// Parse overrides in JSON format and apply them on top of the defaults. See
+    /// [`FieldMaps::from_toml_str`]
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Config)` if `json` cannot be parsed
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        let overrides = serde_json::from_str(json).map_err(|e| AbcError::Config(e.to_string()))?;
+        let mut maps = FieldMaps::default();
+        maps.apply(overrides)?;
+        Ok(maps)
+    }
+
+    /// Read overrides from a `.toml` or `.json` file and apply them on top of the defaults
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Config)` if the file cannot be read or parsed, or if its extension
+    /// is neither `toml` nor `json`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        load_config(
+            path.as_ref(),
+            FieldMaps::from_toml_str,
+            FieldMaps::from_json_str,
+        )
+    }
+}
+
+/// Read a `.toml` or `.json` config file and parse it with the parser matching its extension
+pub(crate) fn load_config<T>(
+    path: &Path,
+    from_toml: fn(&str) -> Result<T>,
+    from_json: fn(&str) -> Result<T>,
+) -> Result<T> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| AbcError::Config(format!("{}: {e}", path.display())))?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => from_toml(&contents),
+        Some("json") => from_json(&contents),
+        _ => Err(AbcError::Config(format!(
+            "{}: config files must be .toml or .json files",
+            path.display()
+        ))),
+    }
+}
+
+static FIELD_MAPS: RwLock<Option<FieldMaps>> = RwLock::new(None);
+
+/// The field maps currently used by every operation in the crate. Until [`set_field_maps`] is
+/// called, these are the defaults from [`FieldMaps::default`]
+pub fn field_maps() -> FieldMaps {
+    FIELD_MAPS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+/// Replace the field maps used by every operation in the crate, eg. with ones loaded through
+/// [`FieldMaps::load`]
+pub fn set_field_maps(maps: FieldMaps) {
+    *FIELD_MAPS.write().unwrap_or_else(|e| e.into_inner()) = Some(maps);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FieldMaps;
+
+    #[test]
+    fn test_overrides() {
+        let maps = FieldMaps::from_toml_str("[inventory]\nupc = 40\n").unwrap();
+        assert_eq!(maps.inventory.get("upc"), Some(40));
+        assert_eq!(maps.inventory.get("list"), Some(25));
+
+        let maps = FieldMaps::from_json_str(r#"{"customer": {"jdf_account": 30}}"#).unwrap();
+        assert_eq!(maps.customer.get("jdf_account"), Some(30));
+        assert_eq!(maps.inventory, FieldMaps::default().inventory);
+
+        assert!(FieldMaps::from_toml_str("[nonsense]\nupc = 40\n").is_err());
+    }
+}
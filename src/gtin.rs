@@ -0,0 +1,96 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::AbcError;
+
+/// A Global Trade Item Number, the barcode of an item, such as a UPC-A (12 digits) or an EAN-13.
+/// Only numbers of 8, 12, 13 or 14 digits whose check digit is correct parse
+///
+/// ```
+/// use abc_uiautomation::Gtin;
+///
+/// let upc: Gtin = "036000291452".parse().unwrap();
+/// assert_eq!(upc.to_string(), "00036000291452");
+/// assert_eq!(upc.to_string_no_padding(), "36000291452");
+/// assert!("036000291453".parse::<Gtin>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Gtin(String);
+
+impl Gtin {
+    /// The digits without the leading zeros, which is how Client4 stores a UPC
+    pub fn to_string_no_padding(&self) -> String {
+        self.0.trim_start_matches('0').to_string()
+    }
+
+    /// The check digit `digits` should end with, computed from every digit before it
+    fn check_digit(digits: &[u8]) -> u8 {
+        let sum: u32 = digits
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, digit)| u32::from(*digit) * if i % 2 == 0 { 3 } else { 1 })
+            .sum();
+        ((10 - sum % 10) % 10) as u8
+    }
+}
+
+/// All 14 digits, padded with leading zeros
+impl fmt::Display for Gtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:0>14}", self.0)
+    }
+}
+
+impl FromStr for Gtin {
+    type Err = AbcError;
+
+    /// Parse a GTIN of 8, 12, 13 or 14 digits, ignoring surrounding whitespace
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || AbcError::Parse {
+            field: "gtin".to_string(),
+            value: s.to_string(),
+        };
+        let text = s.trim();
+        if !matches!(text.len(), 8 | 12 | 13 | 14) || !text.chars().all(|c| c.is_ascii_digit()) {
+            return Err(error());
+        }
+        let digits: Vec<u8> = text.bytes().map(|b| b - b'0').collect();
+        let (check, body) = digits.split_last().ok_or_else(error)?;
+        if Gtin::check_digit(body) != *check {
+            return Err(error());
+        }
+        Ok(Gtin(text.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Gtin;
+
+    #[test]
+    fn test_parse_gtin() {
+        for good in [
+            "036000291452",
+            "4006381333931",
+            "96385074",
+            "00036000291452",
+        ] {
+            let gtin: Gtin = good.parse().unwrap();
+            assert_eq!(gtin.to_string_no_padding(), good.trim_start_matches('0'));
+        }
+        assert_eq!(
+            " 4006381333931 ".parse::<Gtin>().unwrap().to_string(),
+            "04006381333931"
+        );
+        for bad in [
+            "",
+            "036000291453",
+            "03600029145",
+            "03600029145A",
+            "123456789012345",
+        ] {
+            assert!(bad.parse::<Gtin>().is_err(), "{bad:?} should not parse");
+        }
+    }
+}
@@ -1,14 +1,18 @@
-use bigdecimal::BigDecimal;
-use gtin::Gtin;
-use uiautomation::{
-    errors::{ERR_INACTIVE, ERR_NONE, ERR_NOTFOUND},
-    UIAutomation, UIElement,
-};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use bigdecimal::{BigDecimal, RoundingMode};
+use tracing::{debug_span, info_span, warn};
 
+use crate::keys::{Key, Keys};
+use crate::popups::{ADD_TO_UPC, DELETE_RECORD, NOT_FOUND};
+use crate::trace::traced;
 use crate::{
-    create_matcher_wrapper, read_text_box_value, set_text_box_value, set_text_box_value_no_enter,
-    wait, SHORT_WAIT_MS,
+    wait, AbcError, AbcSession, Backend, Comparison, Gtin, InventoryScreen, Money, Result,
 };
+#[cfg(windows)]
+use crate::{UIElement, UiaBackend};
 
 /// An *item* represents a product or some other inventory item
 #[derive(Debug, PartialEq)]
@@ -20,9 +24,9 @@ pub struct Item {
     /// Universal Product Code of the item
     pub upc: Option<String>,
     /// The price of the `Item` for the customer. This appears on `Invoice`s. AKA retail
-    pub list: f32,
+    pub list: Money,
     /// This is the what the business pays for the `Item`. AKA wholesale
-    pub cost: f32,
+    pub cost: Money,
     /// The unique identifier for the vendor who provides the `Item`
     pub vendor_id: Option<String>,
 }
@@ -38,41 +42,243 @@ impl Item {
             sku: "".to_string(),
             description: None,
             upc: None,
-            list: 0f32,
-            cost: 0f32,
+            list: Money::default(),
+            cost: Money::default(),
             vendor_id: None,
         }
     }
 }
 
+/// Inventory fields that have a typed counterpart in [`ItemRecord`]
+const RECORD_FIELDS: &[&str] = &[
+    "sku",
+    "desc",
+    "upc",
+    "vendor",
+    "weight",
+    "list",
+    "cost",
+    "group",
+    "sale_gl",
+    "alt_sku_0",
+    "alt_sku_1",
+    "alt_sku_2",
+];
+
+/// The code of the inventory group an item belongs to, eg. `HW`. Client4 group codes are at most
+/// [`GroupCode::MAX_LEN`] letters and digits. A blank group means the item is in no group, and
+/// is not a code either
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GroupCode(String);
+
+impl GroupCode {
+    /// The most characters a group code may have
+    pub const MAX_LEN: usize = 4;
+
+    /// The code as entered into Client4
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for GroupCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for GroupCode {
+    type Err = AbcError;
+
+    /// Parse a group code, ignoring surrounding whitespace
+    fn from_str(s: &str) -> Result<Self> {
+        let code = s.trim();
+        match !code.is_empty()
+            && code.len() <= GroupCode::MAX_LEN
+            && code.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            true => Ok(GroupCode(code.to_string())),
+            false => Err(AbcError::Parse {
+                field: "group".to_string(),
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Every field of an item on the "Inventory - Items (I)" screen, as read by [`read_item`]. Text
+/// fields are trimmed, and numeric fields are `None` when Client4 leaves them blank
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemRecord {
+    pub sku: String,
+    pub desc: String,
+    pub upc: String,
+    pub vendor: String,
+    pub weight: Option<BigDecimal>,
+    pub list: Option<Money>,
+    pub cost: Option<Money>,
+    pub group: Option<GroupCode>,
+    pub sale_gl: Option<u32>,
+    /// The alternate skus, in the order of [`get_alt_sku`]
+    pub alt_skus: [String; 3],
+    /// Fields added to the inventory [`crate::FieldMap`] beyond the ones above, by name
+    pub other: BTreeMap<String, String>,
+}
+
+/// The changes [`apply_item_patch`] makes to an item. Fields left as `None` are not touched
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemPatch {
+    /// The item to change
+    pub sku: String,
+    pub desc: Option<String>,
+    pub vendor: Option<String>,
+    pub weight: Option<BigDecimal>,
+    pub list: Option<Money>,
+    pub cost: Option<Money>,
+    pub group: Option<GroupCode>,
+    pub sale_gl: Option<u32>,
+    /// The alternate skus, in the order of [`get_alt_sku`]
+    pub alt_skus: [Option<String>; 3],
+}
+
+impl ItemPatch {
+    /// A patch of the item `sku` that changes nothing yet
+    pub fn new(sku: &str) -> Self {
+        ItemPatch {
+            sku: sku.to_string(),
+            ..ItemPatch::default()
+        }
+    }
+}
+
+/// What [`apply_item_patch`] did with one field of an [`ItemPatch`]. Values are given as they
+/// are entered into Client4, eg. prices with two decimals
+#[derive(Debug)]
+pub enum FieldChange {
+    /// The item already held the value, so nothing was written
+    Unchanged,
+    /// The value was written, read back, and saved
+    Changed { from: String, to: String },
+    /// The value was written and read back, but not saved because another field failed
+    Discarded { from: String, to: String },
+    /// Writing the value failed
+    Failed {
+        from: String,
+        to: String,
+        error: AbcError,
+    },
+}
+
+/// The outcome of [`apply_item_patch`], with one entry per field the patch sets, in the order of
+/// the fields of [`ItemPatch`]
+#[derive(Debug)]
+pub struct ItemPatchReport {
+    pub sku: String,
+    pub fields: Vec<(String, FieldChange)>,
+    /// Whether the item was saved. It is not when nothing changed, or when a field failed
+    pub saved: bool,
+    /// Why the edits could not be discarded after a field failed. They are then still on the
+    /// screen, eg. for [`crate::return_to_main_menu`] to deal with
+    pub discard_error: Option<AbcError>,
+}
+
+impl ItemPatchReport {
+    /// Whether any field was written and not discarded
+    pub fn changed(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|(_, change)| matches!(change, FieldChange::Changed { .. }))
+    }
+
+    /// Whether any field could not be written
+    pub fn failed(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|(_, change)| matches!(change, FieldChange::Failed { .. }))
+    }
+}
+
+/// The fields of `record` that hold a value, by their name in the field map, as they are entered
+/// into Client4
+fn entered_fields(record: &ItemRecord) -> Vec<(String, String)> {
+    let mut fields = vec![
+        ("sku".to_string(), record.sku.clone()),
+        ("desc".to_string(), truncate_desc(&record.desc)),
+        ("upc".to_string(), record.upc.clone()),
+        ("vendor".to_string(), record.vendor.clone()),
+    ];
+    if let Some(weight) = &record.weight {
+        let weight = weight.with_scale_round(2, RoundingMode::HalfUp);
+        fields.push(("weight".to_string(), weight.to_string()));
+    }
+    if let Some(list) = &record.list {
+        fields.push(("list".to_string(), list.to_string()));
+    }
+    if let Some(cost) = &record.cost {
+        fields.push(("cost".to_string(), cost.to_string()));
+    }
+    if let Some(group) = &record.group {
+        fields.push(("group".to_string(), group.to_string()));
+    }
+    if let Some(sale_gl) = record.sale_gl {
+        fields.push(("sale_gl".to_string(), sale_gl.to_string()));
+    }
+    for (i, alt_sku) in record.alt_skus.iter().enumerate() {
+        fields.push((format!("alt_sku_{i}"), alt_sku.clone()));
+    }
+    fields.extend(record.other.clone());
+    fields
+        .into_iter()
+        .map(|(field, value)| (field, value.trim().to_string()))
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+}
+
+/// `desc` cut to the 40 characters the description field holds
+fn truncate_desc(desc: &str) -> String {
+    desc.chars().take(40).collect()
+}
+
+/// Check that Client4 loaded `item_number`, now that the sku box reads `sku`
+fn check_loaded_sku(item_number: &str, sku: String) -> Result<()> {
+    if sku != item_number {
+        return Err(AbcError::VerificationFailed {
+            field: "sku".to_string(),
+            expected: item_number.to_string(),
+            actual: sku,
+        });
+    }
+    Ok(())
+}
+
+/// Parse the contents of a field, which Client4 leaves blank when it has no value. Errors name
+/// `field` and quote `value` as it was read
+fn parse_optional<T: FromStr>(field: &str, value: &str) -> Result<Option<T>> {
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+    value.trim().parse().map(Some).map_err(|_| AbcError::Parse {
+        field: field.to_string(),
+        value: value.to_string(),
+    })
+}
+
 /// Control ABC Client4 to navigate to the F10-I screen or the Inventory - Items screen
 ///
 /// # Arguments
 /// * `abc_window` - A reference to the ABC Client4 window to control
 ///
 /// # Returns
-/// A [`UIElement`] representing the Inventory screen
+/// An [`InventoryScreen`] representing the Inventory screen
 ///
 /// #Errors
 /// Forwards any automation errors caused by one of the following:
 /// * Failing to start an instance of the automation controller
 /// * Failing to send keyboard input to ABC
 /// * Critical errors while looking for an existing inventory screen
-pub fn load_inventory_screen(abc_window: &UIElement) -> uiautomation::Result<UIElement> {
-    let automation = UIAutomation::new()?;
-
-    if let Ok(inventory_screen) = create_matcher_wrapper(&automation)?
-        .contains_name("Inventory - Items (I)")
-        .find_first()
-    {
-        return Ok(inventory_screen);
-    }
-
-    abc_window.send_keys("{F10}I", SHORT_WAIT_MS * 3)?;
-
-    create_matcher_wrapper(&automation)?
-        .contains_name("Inventory - Items (I)")
-        .find_first()
+#[cfg(windows)]
+pub fn load_inventory_screen(abc_window: &UIElement) -> Result<InventoryScreen> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).load_inventory_screen()
 }
 
 /// Control the inventory screen of ABC to load a specific item by its unique item number
@@ -82,24 +288,13 @@ pub fn load_inventory_screen(abc_window: &UIElement) -> uiautomation::Result<UIE
 /// * `item_number` - The unique identifier for the item to load
 ///
 /// # Errors
+/// Returns [`AbcError::VerificationFailed`] if the screen does not show `item_number` afterwards.
 /// Forwards any automation errors encountered from any of the following:
 /// * Failing to create an instance of the controller
 /// * Failing to find, click, or send keyboard input to the input field to enter an item number
-pub fn load_item(inventory_window: &UIElement, item_number: &str) -> uiautomation::Result<()> {
-    let automation = UIAutomation::new()?;
-    let item_num_control = create_matcher_wrapper(&automation)?
-        .classname("ThunderRT6TextBox")
-        .from(inventory_window.to_owned())
-        .find_first()?;
-    item_num_control.click()?;
-    item_num_control.send_keys(&format!("{}{{enter}}", item_number), SHORT_WAIT_MS)?;
-    if &get_sku(inventory_window)? != item_number {
-        return Err(uiautomation::Error::new(
-            ERR_NONE,
-            &format!("could not verify that sku {item_number} was entered successfully"),
-        ));
-    }
-    Ok(())
+#[cfg(windows)]
+pub fn load_item(inventory_window: &InventoryScreen, item_number: &str) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).load_item(inventory_window, item_number)
 }
 
 /// Enter the text of `upc` into the UPC input field on an inventory item. Before running this
@@ -112,165 +307,174 @@ pub fn load_item(inventory_window: &UIElement, item_number: &str) -> uiautomatio
 /// * `upc` - The UPC to add to the item listing
 ///
 /// # Errors
+/// Returns [`AbcError::VerificationFailed`] if the UPC field does not read back as `upc`.
 /// Forwards any automation errors caused by any of the following:
-/// * `inventory_window` is not a reference to the "Inventory - Items" screen of ABC Client4
 /// * Failure to create an instance of the controller
 /// * Failing to send keyboard input to the UPC text field
 /// * Failing to find or send input to the confirmation dialog that pops up to confirm adding a
 /// UPC, if it exists.
-pub fn set_upc(inventory_window: &UIElement, upc: &Gtin) -> uiautomation::Result<()> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `set_upc`",
-        ))?;
-    }
-    let automation = UIAutomation::new()?;
-    set_text_box_value_no_enter(inventory_window, 38, upc.to_string_no_padding())?;
-    if get_upc(inventory_window)? != upc.to_string_no_padding() {
-        return Err(uiautomation::Error::new(
-            ERR_NONE,
-            &format!(
-                "could not verify that upc {} was set correctly",
-                upc.to_string_no_padding()
-            ),
-        ));
-    }
-    inventory_window.send_keys("{Enter}", SHORT_WAIT_MS)?;
-    wait(SHORT_WAIT_MS * 3);
-    if let Ok(confirm) = create_matcher_wrapper(&automation)?
-        .classname("ThunderRT6FormDC")
-        .contains_name("Add to UPC")
-        .find_first()
-    {
-        confirm.send_keys("y", SHORT_WAIT_MS)?;
-    }
-    Ok(())
+#[cfg(windows)]
+pub fn set_upc(inventory_window: &InventoryScreen, upc: &Gtin) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).set_upc(inventory_window, upc)
 }
 
-pub fn get_upc(inventory_window: &UIElement) -> uiautomation::Result<String> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `get_upc`",
-        ))?;
-    }
-    read_text_box_value(inventory_window, 38)
+#[cfg(windows)]
+pub fn get_upc(inventory_window: &InventoryScreen) -> Result<String> {
+    AbcSession::new(UiaBackend::new()?).get_upc(inventory_window)
 }
 
-pub fn get_sku(inventory_window: &UIElement) -> uiautomation::Result<String> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `get_sku`",
-        ))?;
-    }
-    read_text_box_value(inventory_window, 0)
+#[cfg(windows)]
+pub fn get_sku(inventory_window: &InventoryScreen) -> Result<String> {
+    AbcSession::new(UiaBackend::new()?).get_sku(inventory_window)
 }
 
-pub fn get_desc(inventory_window: &UIElement) -> uiautomation::Result<String> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `get_desc`",
-        ))?;
-    }
-    read_text_box_value(inventory_window, 1)
+#[cfg(windows)]
+pub fn get_desc(inventory_window: &InventoryScreen) -> Result<String> {
+    AbcSession::new(UiaBackend::new()?).get_desc(inventory_window)
 }
 
-pub fn get_list(inventory_window: &UIElement) -> uiautomation::Result<String> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `set_desc`",
-        ))?;
-    }
-    read_text_box_value(inventory_window, 25)
+#[cfg(windows)]
+pub fn get_list(inventory_window: &InventoryScreen) -> Result<String> {
+    AbcSession::new(UiaBackend::new()?).get_list(inventory_window)
 }
 
-pub fn get_cost(inventory_window: &UIElement) -> uiautomation::Result<String> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `get_cost`",
-        ))?;
-    }
+#[cfg(windows)]
+pub fn get_cost(inventory_window: &InventoryScreen) -> Result<String> {
+    AbcSession::new(UiaBackend::new()?).get_cost(inventory_window)
+}
 
-    read_text_box_value(&inventory_window, 26)
+#[cfg(windows)]
+pub fn get_vendor(inventory_window: &InventoryScreen) -> Result<String> {
+    AbcSession::new(UiaBackend::new()?).get_vendor(inventory_window)
 }
 
-pub fn get_vendor(inventory_window: &UIElement) -> uiautomation::Result<String> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `get_vendor`",
-        ))?;
-    }
+#[cfg(windows)]
+pub fn get_sale_gl(inventory_window: &InventoryScreen) -> Result<String> {
+    AbcSession::new(UiaBackend::new()?).get_sale_gl(inventory_window)
+}
 
-    read_text_box_value(&inventory_window, 14)
+#[cfg(windows)]
+pub fn get_group(inventory_window: &InventoryScreen) -> Result<String> {
+    AbcSession::new(UiaBackend::new()?).get_group(inventory_window)
 }
 
-pub fn get_sale_gl(inventory_window: &UIElement) -> uiautomation::Result<String> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `get_sale_gl`",
-        ))?;
-    }
+#[cfg(windows)]
+pub fn get_weight(inventory_window: &InventoryScreen) -> Result<String> {
+    AbcSession::new(UiaBackend::new()?).get_weight(inventory_window)
+}
+
+/// Read the weight of the loaded item as a decimal, or `None` if it has no weight. [`get_weight`]
+/// returns the text as Client4 shows it
+///
+/// # Errors
+/// Returns [`AbcError::Parse`] naming the field and its contents if it does not hold a number
+#[cfg(windows)]
+pub fn read_weight(inventory_window: &InventoryScreen) -> Result<Option<BigDecimal>> {
+    AbcSession::new(UiaBackend::new()?).read_weight(inventory_window)
+}
 
-    read_text_box_value(&inventory_window, 43)
+/// Read the sale GL account of the loaded item, or `None` if it has none. [`get_sale_gl`] returns
+/// the text as Client4 shows it
+///
+/// # Errors
+/// Returns [`AbcError::Parse`] naming the field and its contents if it is not an account number
+#[cfg(windows)]
+pub fn read_sale_gl(inventory_window: &InventoryScreen) -> Result<Option<u32>> {
+    AbcSession::new(UiaBackend::new()?).read_sale_gl(inventory_window)
 }
 
-pub fn get_group(inventory_window: &UIElement) -> uiautomation::Result<String> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `get_group`",
-        ))?;
-    }
+/// Read the list price of the loaded item, or `None` if it has none. [`get_list`] returns the
+/// text as Client4 shows it
+///
+/// # Errors
+/// Returns [`AbcError::Parse`] naming the field and its contents if it is not an amount
+#[cfg(windows)]
+pub fn read_list(inventory_window: &InventoryScreen) -> Result<Option<Money>> {
+    AbcSession::new(UiaBackend::new()?).read_list(inventory_window)
+}
 
-    read_text_box_value(&inventory_window, 39)
+/// Read the cost of the loaded item, or `None` if it has none. [`get_cost`] returns the text as
+/// Client4 shows it
+///
+/// # Errors
+/// Returns [`AbcError::Parse`] naming the field and its contents if it is not an amount
+#[cfg(windows)]
+pub fn read_cost(inventory_window: &InventoryScreen) -> Result<Option<Money>> {
+    AbcSession::new(UiaBackend::new()?).read_cost(inventory_window)
 }
 
-pub fn get_weight(inventory_window: &UIElement) -> uiautomation::Result<String> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `get_weight`",
-        ))?;
-    }
+/// Read the group of the loaded item, or `None` if it is in no group. [`get_group`] returns the
+/// text as Client4 shows it
+///
+/// # Errors
+/// Returns [`AbcError::Parse`] naming the field and its contents if it is not a [`GroupCode`]
+#[cfg(windows)]
+pub fn read_group(inventory_window: &InventoryScreen) -> Result<Option<GroupCode>> {
+    AbcSession::new(UiaBackend::new()?).read_group(inventory_window)
+}
 
-    read_text_box_value(&inventory_window, 15)
+/// Load the item `sku` into the Inventory screen and read every field of it at once. This
+/// enumerates the text boxes of the screen a single time, instead of once per field like the
+/// `get_*` functions do
+///
+/// # Arguments
+/// * `inventory_window` - The "Inventory - Items" screen of Client4. See [`load_inventory_screen`]
+/// * `sku` - The unique identifier of the item to read
+///
+/// # Errors
+/// Returns [`AbcError::VerificationFailed`] if the item cannot be loaded (see [`load_item`]),
+/// [`AbcError::FieldNotFound`] if the screen lacks a text box the field map refers to, and
+/// [`AbcError::Parse`] if a numeric field holds something other than a number
+#[cfg(windows)]
+pub fn read_item(inventory_window: &InventoryScreen, sku: &str) -> Result<ItemRecord> {
+    AbcSession::new(UiaBackend::new()?).read_item(inventory_window, sku)
+}
+
+/// Load the item named by `patch` and write the fields the patch sets, skipping the ones that
+/// already hold the value. Fields are written through the setters, eg. [`set_list`], so each is
+/// verified. The item is saved if anything changed and every field was written; if a field
+/// fails, the remaining fields are still tried but the changes are discarded, so an item is never
+/// saved half patched. The fields that were written are then reported as
+/// [`FieldChange::Discarded`]
+///
+/// # Arguments
+/// * `inventory_window` - The "Inventory - Items" screen of Client4. See [`load_inventory_screen`]
+/// * `patch` - The item to change and its new values
+///
+/// # Returns
+/// A report of what happened to every field the patch sets, and whether the item was saved
+///
+/// # Errors
+/// Errors writing a field end up in the report, as does an error discarding the changes. Returns
+/// `Err` if the item cannot be read (see [`read_item`]), or if saving the changes fails
+#[cfg(windows)]
+pub fn apply_item_patch(
+    inventory_window: &InventoryScreen,
+    patch: &ItemPatch,
+) -> Result<ItemPatchReport> {
+    AbcSession::new(UiaBackend::new()?).apply_item_patch(inventory_window, patch)
+}
+
+/// Add a new item to Client4 from `record`. The item is created on a fresh record (see
+/// [`crate::send_ctrl_n`]), every field of `record` that holds a value is entered and verified,
+/// a UPC is confirmed with the "Add to UPC" prompt, and the item is saved. Blank fields are left
+/// at Client4's defaults
+///
+/// # Arguments
+/// * `inventory_window` - The "Inventory - Items" screen of Client4. See [`load_inventory_screen`]
+/// * `record` - The item to create. `record.sku` must not be blank
+///
+/// # Returns
+/// The item as Client4 shows it after saving, read with [`read_item`]
+///
+/// # Errors
+/// Returns [`AbcError::AlreadyExists`] without changing anything if an item with the sku is
+/// already in Client4. If a field cannot be entered, the new record is discarded and the error
+/// returned. Returns [`AbcError::VerificationFailed`] naming the field if the saved item differs
+/// from `record`
+#[cfg(windows)]
+pub fn create_item(inventory_window: &InventoryScreen, record: &ItemRecord) -> Result<ItemRecord> {
+    AbcSession::new(UiaBackend::new()?).create_item(inventory_window, record)
 }
 
 /// Empties the UPC input field of the Inventory Screen. Can be used to entirely delete all UPCs or
@@ -285,278 +489,1060 @@ pub fn get_weight(inventory_window: &UIElement) -> uiautomation::Result<String>
 ///
 /// # Errors
 /// Forwards automation errors for any of the following:
-/// * `inventory_window` is not a reference to the "Inventory - Items" screen of ABC Client4
 /// * Failure to create an instance of the controller
 /// * Failing to send keyboard input to the UPC text field
 /// * Failure to find or send input to the dialog that pops up to confirm deletion of UPCs
-pub fn clear_upc(inventory_window: &UIElement, delete_fully: bool) -> uiautomation::Result<()> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `clear_upc`",
-        ))?;
-    }
-    let automation = UIAutomation::new()?;
-    let all_controls = create_matcher_wrapper(&automation)?
-        .classname("ThunderRT6TextBox")
-        .from(inventory_window.to_owned())
-        .find_all()?;
-    let Some(upc_control) = all_controls.get(38) else {
-        return Err(uiautomation::Error::new(
-            ERR_NOTFOUND,
-            "Could not find UPC Control on Inventory Screen",
-        ))?;
-    };
-    upc_control.click()?;
-    upc_control.send_keys("{Delete}", SHORT_WAIT_MS)?;
-
-    if delete_fully {
-        upc_control.send_keys("{Enter}", SHORT_WAIT_MS)?;
-        wait(SHORT_WAIT_MS * 3);
-        let delete_form = create_matcher_wrapper(&automation)?
-            .classname("ThunderRT6FormDC")
-            .name("Delete Record")
-            .find_first()?;
-        delete_form.send_keys("y", SHORT_WAIT_MS)?;
-        wait(SHORT_WAIT_MS * 3);
-    }
-    Ok(())
+#[cfg(windows)]
+pub fn clear_upc(inventory_window: &InventoryScreen, delete_fully: bool) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).clear_upc(inventory_window, delete_fully)
 }
 
-pub fn get_alt_sku(inventory_window: &UIElement, sku_index: u8) -> uiautomation::Result<String> {
-    if sku_index > 2 {
-        return Err(uiautomation::Error::new(
-            ERR_NONE,
-            "sku_index must be 0, 1, or 2",
-        ));
+#[cfg(windows)]
+pub fn get_alt_sku(inventory_window: &InventoryScreen, sku_index: u8) -> Result<String> {
+    AbcSession::new(UiaBackend::new()?).get_alt_sku(inventory_window, sku_index)
+}
+
+#[cfg(windows)]
+pub fn set_alt_sku(inventory_window: &InventoryScreen, alt_sku: &str, sku_index: u8) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).set_alt_sku(inventory_window, alt_sku, sku_index)
+}
+
+#[cfg(windows)]
+pub fn set_desc(inventory_window: &InventoryScreen, desc: &str) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).set_desc(inventory_window, desc)
+}
+
+#[cfg(windows)]
+pub fn set_vendor(inventory_window: &InventoryScreen, vendor: &str) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).set_vendor(inventory_window, vendor)
+}
+
+#[cfg(windows)]
+pub fn set_weight(inventory_window: &InventoryScreen, weight: f64) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).set_weight(inventory_window, weight)
+}
+
+#[cfg(windows)]
+pub fn set_list(inventory_window: &InventoryScreen, list: &Money) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).set_list(inventory_window, list)
+}
+
+#[cfg(windows)]
+pub fn set_cost(inventory_window: &InventoryScreen, cost: &Money) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).set_cost(inventory_window, cost)
+}
+
+#[cfg(windows)]
+pub fn set_group(inventory_window: &InventoryScreen, group: &str) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).set_group(inventory_window, group)
+}
+
+#[cfg(windows)]
+pub fn set_sale_gl(inventory_window: &InventoryScreen, sale_gl: u32) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).set_sale_gl(inventory_window, sale_gl)
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Look up the index of a field on the Inventory screen in the session's field maps
+    fn inventory_field(&self, name: &str) -> Result<usize> {
+        self.field_maps.inventory.index(name)
     }
 
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `get_alt_sku`",
-        ))?;
+    /// Same as [`load_inventory_screen`]
+    pub fn load_inventory_screen(&self) -> Result<InventoryScreen<B::Element>> {
+        traced(
+            debug_span!("load_screen", screen = InventoryScreen::<B::Element>::TITLE),
+            || {
+                if let Ok(inventory_screen) = InventoryScreen::find(&self.backend) {
+                    return Ok(inventory_screen);
+                }
+
+                let abc_window = self.abc_window()?;
+                self.send_keys(
+                    abc_window,
+                    &Keys::new().key(Key::F(10)).text("I"),
+                    self.timing.short() * 3,
+                )?;
+
+                InventoryScreen::find(&self.backend)
+            },
+        )
     }
 
-    read_text_box_value(inventory_window, 35usize + usize::from(sku_index))
-}
+    /// Same as [`load_item`]
+    pub fn load_item(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        item_number: &str,
+    ) -> Result<()> {
+        traced(debug_span!("load_item", sku = item_number), || {
+            let item_num_control =
+                self.text_box_at(inventory_window.element(), self.inventory_field("sku")?)?;
+            self.enter_sku(&item_num_control, item_number)?;
+            check_loaded_sku(item_number, self.get_sku(inventory_window)?)
+        })
+    }
 
-pub fn set_alt_sku(
-    inventory_window: &UIElement,
-    alt_sku: &str,
-    sku_index: u8,
-) -> uiautomation::Result<()> {
-    if sku_index > 2 {
-        return Err(uiautomation::Error::new(
-            ERR_NONE,
-            "sku_index must be 0, 1, or 2",
-        ));
+    /// Type `item_number` into the sku box `item_num_control` and press Enter, which makes
+    /// Client4 look it up
+    fn enter_sku(&self, item_num_control: &B::Element, item_number: &str) -> Result<()> {
+        self.backend.click(item_num_control)?;
+        self.send_keys(
+            item_num_control,
+            &Keys::new().text(item_number).key(Key::Enter),
+            self.timing.short(),
+        )
     }
 
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `set_alt_sku`",
-        ))?;
-    }
-    // Verify that the alt sku was entered correctly. If it wasn't, try again, then fail
-    for _ in 0..2 {
-        set_text_box_value(
-            &inventory_window,
-            35_usize + usize::from(sku_index),
-            alt_sku,
-        )?;
-        if get_alt_sku(inventory_window, sku_index)?.trim() == alt_sku.trim() {
-            return Ok(());
-        }
+    /// Same as [`set_upc`]
+    pub fn set_upc(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        upc: &Gtin,
+    ) -> Result<()> {
+        self.enter_upc(inventory_window, &upc.to_string_no_padding())
     }
-    return Err(uiautomation::Error::new(
-        ERR_NONE,
-        "could not verify that alt sku was entered correctly",
-    ));
-}
-
-pub fn set_desc(inventory_window: &UIElement, desc: &str) -> uiautomation::Result<()> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `set_desc`",
-        ))?;
-    }
-
-    let mut desc = desc.to_string();
-    desc.truncate(40);
-    for _ in 0..2 {
-        set_text_box_value(&inventory_window, 1, &desc)?;
-        if get_desc(inventory_window)?.trim() == desc.trim() {
-            return Ok(());
-        }
+
+    /// Enter `upc` into the UPC field, verify it, and confirm the "Add to UPC" prompt
+    fn enter_upc(&self, inventory_window: &InventoryScreen<B::Element>, upc: &str) -> Result<()> {
+        traced(debug_span!("set_upc", upc), || {
+            self.set_text_box_value_no_enter(
+                inventory_window.element(),
+                self.inventory_field("upc")?,
+                upc,
+            )?;
+            let entered_upc = self.get_upc(inventory_window)?;
+            if entered_upc != upc {
+                return Err(AbcError::VerificationFailed {
+                    field: "upc".to_string(),
+                    expected: upc.to_string(),
+                    actual: entered_upc,
+                });
+            }
+            self.send_keys(
+                inventory_window.element(),
+                &Keys::new().key(Key::Enter),
+                self.timing.short(),
+            )?;
+            if self.wait_for_popup(
+                "add_to_upc_popup",
+                self.timing.short_wait_ms * 30,
+                ADD_TO_UPC,
+            )? {
+                self.handle_popups()?;
+            }
+            Ok(())
+        })
     }
-    return Err(uiautomation::Error::new(
-        ERR_NONE,
-        &format!("could not verify that desc {desc} was entered correctly"),
-    ));
-}
 
-pub fn set_vendor(inventory_window: &UIElement, vendor: &str) -> uiautomation::Result<()> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `set_vendor`",
-        ))?;
+    /// Same as [`get_upc`]
+    pub fn get_upc(&self, inventory_window: &InventoryScreen<B::Element>) -> Result<String> {
+        self.read_text_box_value(inventory_window.element(), self.inventory_field("upc")?)
     }
 
-    for _ in 0..2 {
-        set_text_box_value(&inventory_window, 14, vendor)?;
-        if get_vendor(inventory_window)?.trim() == vendor.trim() {
-            return Ok(());
-        }
+    /// Same as [`get_sku`]
+    pub fn get_sku(&self, inventory_window: &InventoryScreen<B::Element>) -> Result<String> {
+        self.read_text_box_value(inventory_window.element(), self.inventory_field("sku")?)
     }
 
-    return Err(uiautomation::Error::new(
-        ERR_NONE,
-        &format!("could not verify that vendor {vendor} was entered correctly"),
-    ));
-}
+    /// Same as [`get_desc`]
+    pub fn get_desc(&self, inventory_window: &InventoryScreen<B::Element>) -> Result<String> {
+        self.read_text_box_value(inventory_window.element(), self.inventory_field("desc")?)
+    }
 
-pub fn set_weight(inventory_window: &UIElement, weight: f64) -> uiautomation::Result<()> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `set_weight`",
-        ))?;
+    /// Same as [`get_list`]
+    pub fn get_list(&self, inventory_window: &InventoryScreen<B::Element>) -> Result<String> {
+        self.read_text_box_value(inventory_window.element(), self.inventory_field("list")?)
     }
 
-    for _ in 0..2 {
-        set_text_box_value(&inventory_window, 15, format!("{:.2}", weight))?;
-        if get_weight(inventory_window)? == format!("{:.2}", weight) {
-            return Ok(());
-        }
+    /// Same as [`get_cost`]
+    pub fn get_cost(&self, inventory_window: &InventoryScreen<B::Element>) -> Result<String> {
+        self.read_text_box_value(inventory_window.element(), self.inventory_field("cost")?)
     }
-    return Err(uiautomation::Error::new(
-        ERR_NONE,
-        &format!("could not verify that weight {weight} was entered correctly"),
-    ));
-}
 
-pub fn set_list(inventory_window: &UIElement, list: &BigDecimal) -> uiautomation::Result<()> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `set_list`",
-        ))?;
+    /// Same as [`get_vendor`]
+    pub fn get_vendor(&self, inventory_window: &InventoryScreen<B::Element>) -> Result<String> {
+        self.read_text_box_value(inventory_window.element(), self.inventory_field("vendor")?)
     }
 
-    // If setting the list price fails, try one more time, then fail
-    for _ in 0..2 {
-        set_text_box_value(&inventory_window, 25, format!("{:.2}", list))?;
-        if get_list(inventory_window)? == format!("{:.2}", list) {
-            return Ok(());
-        }
+    /// Same as [`get_sale_gl`]
+    pub fn get_sale_gl(&self, inventory_window: &InventoryScreen<B::Element>) -> Result<String> {
+        self.read_text_box_value(inventory_window.element(), self.inventory_field("sale_gl")?)
     }
-    return Err(uiautomation::Error::new(
-        ERR_NONE,
-        "failed to set list price twice",
-    ));
-}
 
-pub fn set_cost(inventory_window: &UIElement, cost: &BigDecimal) -> uiautomation::Result<()> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `set_cost`",
-        ))?;
+    /// Same as [`get_group`]
+    pub fn get_group(&self, inventory_window: &InventoryScreen<B::Element>) -> Result<String> {
+        self.read_text_box_value(inventory_window.element(), self.inventory_field("group")?)
     }
 
-    for _ in 0..2 {
-        set_text_box_value(&inventory_window, 26, format!("{:.2}", cost))?;
-        if get_cost(inventory_window)? == format!("{:.2}", cost) {
-            return Ok(());
-        }
+    /// Same as [`get_weight`]
+    pub fn get_weight(&self, inventory_window: &InventoryScreen<B::Element>) -> Result<String> {
+        self.read_text_box_value(inventory_window.element(), self.inventory_field("weight")?)
     }
-    return Err(uiautomation::Error::new(
-        ERR_NONE,
-        &format!("could not verify that cost {cost} was entered correctly"),
-    ));
-}
 
-pub fn set_group(inventory_window: &UIElement, group: &str) -> uiautomation::Result<()> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `set_group`",
-        ))?;
+    /// Same as [`read_weight`]
+    pub fn read_weight(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+    ) -> Result<Option<BigDecimal>> {
+        parse_optional("weight", &self.get_weight(inventory_window)?)
+    }
+
+    /// Same as [`read_sale_gl`]
+    pub fn read_sale_gl(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+    ) -> Result<Option<u32>> {
+        parse_optional("sale_gl", &self.get_sale_gl(inventory_window)?)
+    }
+
+    /// Same as [`read_list`]
+    pub fn read_list(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+    ) -> Result<Option<Money>> {
+        parse_optional("list", &self.get_list(inventory_window)?)
+    }
+
+    /// Same as [`read_cost`]
+    pub fn read_cost(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+    ) -> Result<Option<Money>> {
+        parse_optional("cost", &self.get_cost(inventory_window)?)
+    }
+
+    /// Same as [`read_group`]
+    pub fn read_group(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+    ) -> Result<Option<GroupCode>> {
+        parse_optional("group", &self.get_group(inventory_window)?)
+    }
+
+    /// Same as [`read_item`]
+    pub fn read_item(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        sku: &str,
+    ) -> Result<ItemRecord> {
+        traced(debug_span!("read_item", sku), || {
+            // The text boxes are listed once, both to load the item and to read it back
+            let text_boxes = self.backend.text_boxes(inventory_window.element())?;
+            let not_found = |index: usize| AbcError::FieldNotFound {
+                screen: InventoryScreen::<B::Element>::TITLE.to_string(),
+                index,
+            };
+            let sku_index = self.inventory_field("sku")?;
+            let sku_box = text_boxes
+                .get(sku_index)
+                .ok_or_else(|| not_found(sku_index))?;
+            self.enter_sku(sku_box, sku)?;
+            let values = text_boxes
+                .iter()
+                .map(|text_box| self.backend.value(text_box))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            check_loaded_sku(sku, values[sku_index].clone())?;
+            let field = |name: &str| -> Result<String> {
+                let index = self.inventory_field(name)?;
+                match values.get(index) {
+                    Some(value) => Ok(value.trim().to_string()),
+                    None => Err(not_found(index)),
+                }
+            };
+
+            let mut other = BTreeMap::new();
+            for (name, _) in self.field_maps.inventory.iter() {
+                if !RECORD_FIELDS.contains(&name) {
+                    other.insert(name.to_string(), field(name)?);
+                }
+            }
+            Ok(ItemRecord {
+                sku: field("sku")?,
+                desc: field("desc")?,
+                upc: field("upc")?,
+                vendor: field("vendor")?,
+                weight: parse_optional("weight", &field("weight")?)?,
+                list: parse_optional("list", &field("list")?)?,
+                cost: parse_optional("cost", &field("cost")?)?,
+                group: parse_optional("group", &field("group")?)?,
+                sale_gl: parse_optional("sale_gl", &field("sale_gl")?)?,
+                alt_skus: [
+                    field("alt_sku_0")?,
+                    field("alt_sku_1")?,
+                    field("alt_sku_2")?,
+                ],
+                other,
+            })
+        })
+    }
+
+    /// Same as [`apply_item_patch`]
+    pub fn apply_item_patch(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        patch: &ItemPatch,
+    ) -> Result<ItemPatchReport> {
+        traced(info_span!("apply_item_patch", sku = patch.sku), || {
+            let item = self.read_item(inventory_window, &patch.sku)?;
+            let mut fields = Vec::new();
+            let mut apply = |field: &str,
+                             unchanged: bool,
+                             from: String,
+                             to: String,
+                             write: &dyn Fn() -> Result<()>| {
+                let change = match unchanged {
+                    true => FieldChange::Unchanged,
+                    false => match write() {
+                        Ok(()) => FieldChange::Changed { from, to },
+                        Err(error) => FieldChange::Failed { from, to, error },
+                    },
+                };
+                fields.push((field.to_string(), change));
+            };
+            let price =
+                |value: &Option<Money>| value.as_ref().map(Money::to_string).unwrap_or_default();
+
+            if let Some(desc) = &patch.desc {
+                let to = truncate_desc(desc).trim().to_string();
+                apply("desc", item.desc == to, item.desc.clone(), to, &|| {
+                    self.set_desc(inventory_window, desc)
+                });
+            }
+            if let Some(vendor) = &patch.vendor {
+                let to = vendor.trim().to_string();
+                apply(
+                    "vendor",
+                    item.vendor == to,
+                    item.vendor.clone(),
+                    to,
+                    &|| self.set_vendor(inventory_window, vendor),
+                );
+            }
+            if let Some(weight) = &patch.weight {
+                let rounded = weight.with_scale_round(2, RoundingMode::HalfUp);
+                let from = item.weight.as_ref().map(BigDecimal::to_string);
+                apply(
+                    "weight",
+                    item.weight.as_ref() == Some(&rounded),
+                    from.unwrap_or_default(),
+                    rounded.to_string(),
+                    &|| self.enter_weight(inventory_window, weight),
+                );
+            }
+            if let Some(list) = &patch.list {
+                let unchanged = item.list.as_ref().map(Money::to_cents) == Some(list.to_cents());
+                apply(
+                    "list",
+                    unchanged,
+                    price(&item.list),
+                    list.to_string(),
+                    &|| self.set_list(inventory_window, list),
+                );
+            }
+            if let Some(cost) = &patch.cost {
+                let unchanged = item.cost.as_ref().map(Money::to_cents) == Some(cost.to_cents());
+                apply(
+                    "cost",
+                    unchanged,
+                    price(&item.cost),
+                    cost.to_string(),
+                    &|| self.set_cost(inventory_window, cost),
+                );
+            }
+            if let Some(group) = &patch.group {
+                let from = item.group.as_ref().map(GroupCode::to_string);
+                apply(
+                    "group",
+                    item.group.as_ref() == Some(group),
+                    from.unwrap_or_default(),
+                    group.to_string(),
+                    &|| self.set_group(inventory_window, group.as_str()),
+                );
+            }
+            if let Some(sale_gl) = patch.sale_gl {
+                let from = item.sale_gl.map(|gl| gl.to_string()).unwrap_or_default();
+                let unchanged = item.sale_gl == Some(sale_gl);
+                apply("sale_gl", unchanged, from, sale_gl.to_string(), &|| {
+                    self.set_sale_gl(inventory_window, sale_gl)
+                });
+            }
+            for (i, alt_sku) in patch.alt_skus.iter().enumerate() {
+                if let Some(alt_sku) = alt_sku {
+                    let from = item.alt_skus[i].clone();
+                    let to = alt_sku.trim().to_string();
+                    apply(&format!("alt_sku_{i}"), from == to, from, to, &|| {
+                        self.set_alt_sku(inventory_window, alt_sku, i as u8)
+                    });
+                }
+            }
+
+            let mut report = ItemPatchReport {
+                sku: patch.sku.clone(),
+                fields,
+                saved: false,
+                discard_error: None,
+            };
+            if report.failed() {
+                // Nothing is saved, so the fields that were written did not change after all
+                for (_, change) in &mut report.fields {
+                    if let FieldChange::Changed { from, to } = change {
+                        *change = FieldChange::Discarded {
+                            from: std::mem::take(from),
+                            to: std::mem::take(to),
+                        };
+                    }
+                }
+                if let Err(e) = self.send_ctrl_n(false) {
+                    warn!(error = %e, "could not discard the patch");
+                    report.discard_error = Some(e);
+                }
+            } else if report.changed() {
+                self.send_ctrl_n(true)?;
+                report.saved = true;
+            }
+            Ok(report)
+        })
+    }
+
+    /// Same as [`create_item`]
+    pub fn create_item(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        record: &ItemRecord,
+    ) -> Result<ItemRecord> {
+        traced(info_span!("create_item", sku = record.sku), || {
+            let sku = record.sku.trim();
+            if sku.is_empty() {
+                return Err(AbcError::InvalidArgument(
+                    "cannot create an item without a sku".to_string(),
+                ));
+            }
+            if self.item_exists(inventory_window, sku)? {
+                return Err(AbcError::AlreadyExists {
+                    record: sku.to_string(),
+                });
+            }
+
+            self.send_ctrl_n(false)?;
+            if let Err(e) = self.fill_item(inventory_window, record) {
+                // The error that matters is the one entering the item, not any from cleaning up
+                if let Err(discard) = self.send_ctrl_n(false) {
+                    warn!(error = %discard, "could not discard the new item");
+                }
+                return Err(e);
+            }
+            self.send_ctrl_n(true)?;
+
+            let created = self.read_item(inventory_window, sku)?;
+            let actual: BTreeMap<String, String> = entered_fields(&created).into_iter().collect();
+            for (field, expected) in entered_fields(record) {
+                let actual = actual.get(&field).cloned().unwrap_or_default();
+                let comparison = match field.as_str() {
+                    "list" | "cost" => Comparison::Amount,
+                    _ => Comparison::Trimmed,
+                };
+                if !comparison.matches(&expected, &actual) {
+                    return Err(AbcError::VerificationFailed {
+                        field,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+            Ok(created)
+        })
+    }
+
+    /// Whether an item called `sku` is in Client4, judged by what Client4 shows once the sku is
+    /// entered: it is not if Client4 answers with a [`NOT_FOUND`] popup, or if the sku box does
+    /// not hold the sku afterwards. Any other popup is handled through the session's
+    /// [`crate::PopupRegistry`]
+    fn item_exists(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        sku: &str,
+    ) -> Result<bool> {
+        traced(debug_span!("item_exists", sku), || {
+            let sku_box =
+                self.text_box_at(inventory_window.element(), self.inventory_field("sku")?)?;
+            self.backend.click(&sku_box)?;
+            self.send_keys(
+                &sku_box,
+                &Keys::new().key(Key::Delete).text(sku).key(Key::Enter),
+                self.timing.short(),
+            )?;
+            self.wait_for_popup(
+                "item_not_found_popup",
+                self.timing.short_wait_ms * 5,
+                NOT_FOUND,
+            )?;
+            let seen = self.handle_popups()?;
+            if seen
+                .iter()
+                .any(|popup| popup.info.title.contains(NOT_FOUND))
+            {
+                return Ok(false);
+            }
+            Ok(self.get_sku(inventory_window)?.trim() == sku)
+        })
     }
 
-    for _ in 0..2 {
-        set_text_box_value(&inventory_window, 39, group.to_string())?;
-        if &get_group(inventory_window)? == group {
-            return Ok(());
+    /// Enter every field of `record` that holds a value into the fresh record on the screen
+    fn fill_item(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        record: &ItemRecord,
+    ) -> Result<()> {
+        let element = inventory_window.element();
+        self.write_verified(
+            element,
+            self.inventory_field("sku")?,
+            "sku",
+            record.sku.trim(),
+            Comparison::Trimmed,
+        )?;
+        // Entering the sku looks it up like any other, so Client4 answers with a NOT_FOUND popup
+        if self.wait_for_popup(
+            "item_not_found_popup",
+            self.timing.short_wait_ms * 5,
+            NOT_FOUND,
+        )? {
+            self.handle_popups()?;
+        }
+        if !record.desc.trim().is_empty() {
+            self.set_desc(inventory_window, &record.desc)?;
+        }
+        if !record.vendor.trim().is_empty() {
+            self.set_vendor(inventory_window, &record.vendor)?;
+        }
+        if let Some(weight) = &record.weight {
+            self.enter_weight(inventory_window, weight)?;
+        }
+        if let Some(list) = &record.list {
+            self.set_list(inventory_window, list)?;
+        }
+        if let Some(cost) = &record.cost {
+            self.set_cost(inventory_window, cost)?;
+        }
+        if let Some(group) = &record.group {
+            self.set_group(inventory_window, group.as_str())?;
+        }
+        if let Some(sale_gl) = record.sale_gl {
+            self.set_sale_gl(inventory_window, sale_gl)?;
         }
+        for (i, alt_sku) in record.alt_skus.iter().enumerate() {
+            if !alt_sku.trim().is_empty() {
+                self.set_alt_sku(inventory_window, alt_sku, i as u8)?;
+            }
+        }
+        for (name, value) in &record.other {
+            if !value.trim().is_empty() {
+                self.write_verified(
+                    element,
+                    self.inventory_field(name)?,
+                    name,
+                    value,
+                    Comparison::Trimmed,
+                )?;
+            }
+        }
+        if !record.upc.trim().is_empty() {
+            self.enter_upc(inventory_window, record.upc.trim())?;
+        }
+        Ok(())
     }
-    return Err(uiautomation::Error::new(
-        ERR_NONE,
-        &format!("could not verify that group {group} was entered correctly"),
-    ));
-}
 
-pub fn set_sale_gl(inventory_window: &UIElement, sale_gl: u32) -> uiautomation::Result<()> {
-    if !inventory_window
-        .get_name()?
-        .starts_with("Inventory - Items (I)")
-    {
-        return Err(uiautomation::Error::new(
-            ERR_INACTIVE,
-            "Inventory window is not open in `set_sale_gl`",
-        ))?;
+    /// Same as [`clear_upc`]
+    pub fn clear_upc(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        delete_fully: bool,
+    ) -> Result<()> {
+        traced(debug_span!("clear_upc", delete_fully), || {
+            let upc_control =
+                self.text_box_at(inventory_window.element(), self.inventory_field("upc")?)?;
+            self.backend.click(&upc_control)?;
+            self.send_keys(
+                &upc_control,
+                &Keys::new().key(Key::Delete),
+                self.timing.short(),
+            )?;
+
+            if delete_fully {
+                self.send_keys(
+                    &upc_control,
+                    &Keys::new().key(Key::Enter),
+                    self.timing.short(),
+                )?;
+                if !self.wait_for_popup(
+                    "delete_record_popup",
+                    self.timing.short_wait_ms * 30,
+                    DELETE_RECORD,
+                )? {
+                    return Err(AbcError::Timeout {
+                        waiting_for: "the Delete Record confirmation".to_string(),
+                    });
+                }
+                self.handle_popups()?;
+                wait(
+                    self.timing
+                        .delay("clear_upc", self.timing.short_wait_ms * 3),
+                );
+            }
+            Ok(())
+        })
     }
 
-    for _ in 0..2 {
-        set_text_box_value(&inventory_window, 43, sale_gl.to_string())?;
-        if get_sale_gl(inventory_window)?.trim() == sale_gl.to_string().trim() {
-            return Ok(());
+    /// Same as [`get_alt_sku`]
+    pub fn get_alt_sku(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        sku_index: u8,
+    ) -> Result<String> {
+        if sku_index > 2 {
+            return Err(AbcError::InvalidArgument(
+                "sku_index must be 0, 1, or 2".to_string(),
+            ));
         }
+
+        self.read_text_box_value(
+            inventory_window.element(),
+            self.inventory_field(&format!("alt_sku_{sku_index}"))?,
+        )
+    }
+
+    /// Same as [`set_alt_sku`]
+    pub fn set_alt_sku(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        alt_sku: &str,
+        sku_index: u8,
+    ) -> Result<()> {
+        if sku_index > 2 {
+            return Err(AbcError::InvalidArgument(
+                "sku_index must be 0, 1, or 2".to_string(),
+            ));
+        }
+
+        self.write_verified(
+            inventory_window.element(),
+            self.inventory_field(&format!("alt_sku_{sku_index}"))?,
+            &format!("alt sku {sku_index}"),
+            alt_sku,
+            Comparison::Trimmed,
+        )
+    }
+
+    /// Same as [`set_desc`]
+    pub fn set_desc(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        desc: &str,
+    ) -> Result<()> {
+        self.write_verified(
+            inventory_window.element(),
+            self.inventory_field("desc")?,
+            "desc",
+            &truncate_desc(desc),
+            Comparison::Trimmed,
+        )
+    }
+
+    /// Same as [`set_vendor`]
+    pub fn set_vendor(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        vendor: &str,
+    ) -> Result<()> {
+        self.write_verified(
+            inventory_window.element(),
+            self.inventory_field("vendor")?,
+            "vendor",
+            vendor,
+            Comparison::Trimmed,
+        )
+    }
+
+    /// Same as [`set_weight`]
+    pub fn set_weight(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        weight: f64,
+    ) -> Result<()> {
+        let parsed = BigDecimal::from_str(&weight.to_string()).map_err(|_| AbcError::Parse {
+            field: "weight".to_string(),
+            value: weight.to_string(),
+        })?;
+        self.enter_weight(inventory_window, &parsed)
+    }
+
+    /// Enter `weight` rounded half up to the two decimals the field shows. Every weight is
+    /// entered this way, whether it is set on its own, patched or part of a new item
+    fn enter_weight(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        weight: &BigDecimal,
+    ) -> Result<()> {
+        self.write_verified(
+            inventory_window.element(),
+            self.inventory_field("weight")?,
+            "weight",
+            &weight.with_scale_round(2, RoundingMode::HalfUp).to_string(),
+            Comparison::Exact,
+        )
+    }
+
+    /// Same as [`set_list`]
+    pub fn set_list(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        list: &Money,
+    ) -> Result<()> {
+        self.write_verified(
+            inventory_window.element(),
+            self.inventory_field("list")?,
+            "list",
+            &list.to_string(),
+            Comparison::Amount,
+        )
+    }
+
+    /// Same as [`set_cost`]
+    pub fn set_cost(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        cost: &Money,
+    ) -> Result<()> {
+        self.write_verified(
+            inventory_window.element(),
+            self.inventory_field("cost")?,
+            "cost",
+            &cost.to_string(),
+            Comparison::Amount,
+        )
+    }
+
+    /// Same as [`set_group`]
+    pub fn set_group(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        group: &str,
+    ) -> Result<()> {
+        self.write_verified(
+            inventory_window.element(),
+            self.inventory_field("group")?,
+            "group",
+            group,
+            Comparison::Exact,
+        )
+    }
+
+    /// Same as [`set_sale_gl`]
+    pub fn set_sale_gl(
+        &self,
+        inventory_window: &InventoryScreen<B::Element>,
+        sale_gl: u32,
+    ) -> Result<()> {
+        self.write_verified(
+            inventory_window.element(),
+            self.inventory_field("sale_gl")?,
+            "sale gl",
+            &sale_gl.to_string(),
+            Comparison::Trimmed,
+        )
     }
-    return Err(uiautomation::Error::new(
-        ERR_NONE,
-        &format!("could not verify that sale gl {sale_gl} was entered correctly"),
-    ));
 }
 
 #[cfg(test)]
 mod tests {
     use bigdecimal::BigDecimal;
 
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    use super::{FieldChange, GroupCode, ItemPatch, ItemRecord};
+    use crate::backend::FakeBackend;
+    use crate::popups::{ADD_TO_UPC, NOT_FOUND, SAVE_CHANGES};
+    use crate::{AbcError, AbcSession, Backend, FieldMap, FieldMaps, InventoryScreen, Timing};
+
     #[test]
     fn test_big_decimal() {
         let price: BigDecimal = "4.99".parse().unwrap();
         assert_eq!(format!("{}", price), String::from("4.99"));
     }
+
+    #[test]
+    fn test_read_item() {
+        let fake = FakeBackend::new();
+        let screen = fake.add_screen("Inventory - Items (I)");
+        fake.add_text_boxes(screen, &[""; 44]);
+        fake.on_keys("WIDGET{Enter}", move |fake, _, _| {
+            for (index, value) in [
+                (1, "BLUE WIDGET  "),
+                (14, "ACME"),
+                (15, ".5"),
+                (25, "4.99"),
+                (26, ""),
+                (36, "WIDGET-B"),
+                (39, "HW"),
+                (40, "BIN 7"),
+                (43, "4000"),
+            ] {
+                fake.set_text_box(screen, index, value);
+            }
+        });
+
+        let fields = FieldMaps {
+            inventory: FieldMap::inventory().with("location", 40),
+            ..FieldMaps::default()
+        };
+        let session = AbcSession::new(fake).with_field_maps(fields);
+        let inventory_window = InventoryScreen::new(&session.backend, screen).unwrap();
+        let item = session.read_item(&inventory_window, "WIDGET").unwrap();
+        assert_eq!(item.sku, "WIDGET");
+        assert_eq!(item.desc, "BLUE WIDGET");
+        assert_eq!(item.vendor, "ACME");
+        assert_eq!(item.weight, Some("0.5".parse().unwrap()));
+        assert_eq!(item.list, Some("4.99".parse().unwrap()));
+        assert_eq!(item.cost, None);
+        assert_eq!(item.sale_gl, Some(4000));
+        assert_eq!(item.alt_skus, ["", "WIDGET-B", ""]);
+        assert_eq!(item.other["location"], "BIN 7");
+    }
+
+    #[test]
+    fn test_apply_item_patch() {
+        let fake = FakeBackend::new();
+        let abc_window = fake.add_screen("ABC Accounting Client");
+        let screen = fake.add_screen("Inventory - Items (I)");
+        fake.add_text_boxes(screen, &[""; 44]);
+        fake.on_keys("WIDGET{Enter}", move |fake, _, _| {
+            fake.set_text_box(screen, 1, "BLUE WIDGET");
+            fake.set_text_box(screen, 15, ".5");
+            fake.set_text_box(screen, 25, "4.99");
+            fake.set_text_box(screen, 39, "HW");
+        });
+        // Client4 asks to save the edited item, then starts a blank one
+        fake.on_keys("{Ctrl}N", move |fake, _, _| {
+            fake.add_popup(SAVE_CHANGES);
+            fake.set_text_box(screen, 0, "");
+        });
+        // Client4 refuses an unknown group and puts the old one back
+        fake.on_keys("{Delete}XX", move |fake, _, _| {
+            fake.set_text_box(screen, 39, "HW")
+        });
+
+        let session = AbcSession::with_window(fake, abc_window)
+            .with_timing(Timing::default().with_scale(0.1));
+        let inventory_window = InventoryScreen::new(&session.backend, screen).unwrap();
+        let mut patch = ItemPatch::new("WIDGET");
+        patch.desc = Some("BLUE WIDGET".to_string());
+        patch.list = Some("5.25".parse().unwrap());
+        let report = session.apply_item_patch(&inventory_window, &patch).unwrap();
+        assert!(report.saved);
+        assert!(matches!(report.fields[0], (_, FieldChange::Unchanged)));
+        match &report.fields[1] {
+            (field, FieldChange::Changed { from, to }) => {
+                assert_eq!(
+                    (field.as_str(), from.as_str(), to.as_str()),
+                    ("list", "4.99", "5.25")
+                )
+            }
+            other => panic!("expected a changed list, got {other:?}"),
+        }
+        let sent = session.backend.sent_keys();
+        assert!(!sent
+            .iter()
+            .any(|(_, keys)| keys.contains("BLUE WIDGET{Enter}")));
+
+        // A failed field keeps every field from being saved. Descriptions are cut by characters
+        patch.desc = Some("ÉCROU À OREILLES INOXYDABLE — 3/8 PO, PAQUET DE 10".to_string());
+        patch.weight = Some("0.500".parse().unwrap());
+        patch.group = Some("XX".parse().unwrap());
+        let report = session.apply_item_patch(&inventory_window, &patch).unwrap();
+        assert!(!report.saved);
+        assert!(report.discard_error.is_none());
+        match &report.fields[0] {
+            (_, FieldChange::Discarded { to, .. }) => {
+                assert_eq!(to, "ÉCROU À OREILLES INOXYDABLE — 3/8 PO, PA")
+            }
+            other => panic!("expected a discarded desc, got {other:?}"),
+        }
+        assert!(matches!(report.fields[1], (_, FieldChange::Unchanged)));
+        assert!(matches!(
+            report.fields[2],
+            (_, FieldChange::Discarded { .. })
+        ));
+        assert!(matches!(report.fields[3], (_, FieldChange::Failed { .. })));
+
+        // Values are compared as Client4 would keep them, to two decimals
+        let mut patch = ItemPatch::new("WIDGET");
+        patch.weight = Some("0.504".parse().unwrap());
+        patch.list = Some("4.994".parse().unwrap());
+        let report = session.apply_item_patch(&inventory_window, &patch).unwrap();
+        assert!(report
+            .fields
+            .iter()
+            .all(|(_, change)| matches!(change, FieldChange::Unchanged)));
+    }
+
+    #[test]
+    fn test_typed_getters() {
+        let fake = FakeBackend::new();
+        let screen = fake.add_screen("Inventory - Items (I)");
+        fake.add_text_boxes(screen, &[""; 44]);
+        for (index, value) in [
+            (15, "2 LB"),
+            (25, "1,299.00"),
+            (26, ".20"),
+            (39, "HW "),
+            (43, " 4000 "),
+        ] {
+            fake.set_text_box(screen, index, value);
+        }
+
+        let session = AbcSession::new(fake);
+        let inventory_window = InventoryScreen::new(&session.backend, screen).unwrap();
+        match session.read_weight(&inventory_window) {
+            Err(AbcError::Parse { field, value }) => {
+                assert_eq!((field.as_str(), value.as_str()), ("weight", "2 LB"))
+            }
+            other => panic!("expected Parse, got {other:?}"),
+        }
+        assert_eq!(session.read_sale_gl(&inventory_window).unwrap(), Some(4000));
+        let list = session.read_list(&inventory_window).unwrap().unwrap();
+        assert_eq!(list.to_string(), "1299.00");
+        let cost = session.read_cost(&inventory_window).unwrap().unwrap();
+        assert_eq!(cost.to_string(), "0.20");
+        let group = session.read_group(&inventory_window).unwrap().unwrap();
+        assert_eq!(group.as_str(), "HW");
+        for bad in [" ", "H W", "LAWNS", "LAWN&GARDEN"] {
+            assert!(
+                bad.parse::<GroupCode>().is_err(),
+                "{bad:?} should not parse"
+            );
+        }
+
+        // A malformed group is reported, while the string getter still returns it as is
+        session
+            .backend
+            .set_text_box(*inventory_window.element(), 39, "H&W ");
+        match session.read_group(&inventory_window) {
+            Err(AbcError::Parse { field, value }) => {
+                assert_eq!((field.as_str(), value.as_str()), ("group", "H&W "))
+            }
+            other => panic!("expected Parse, got {other:?}"),
+        }
+        assert_eq!(session.get_group(&inventory_window).unwrap(), "H&W ");
+    }
+
+    #[test]
+    fn test_create_item() {
+        let fake = FakeBackend::new();
+        let abc_window = fake.add_screen("ABC Accounting Client");
+        let screen = fake.add_screen("Inventory - Items (I)");
+        let text_boxes = fake.add_text_boxes(screen, &[""; 44]);
+        let sku_box = text_boxes[0];
+        // Client4 keeps saved items by sku, and starts a blank record on Ctrl+N
+        let saved: Rc<RefCell<HashMap<String, Vec<String>>>> = Rc::default();
+        let items = saved.clone();
+        fake.on_keys("{Ctrl}N", move |fake, _, _| {
+            fake.add_popup(SAVE_CHANGES);
+            let values = fake.text_box_values(&screen).unwrap();
+            if !values[1].is_empty() {
+                items.borrow_mut().insert(values[0].clone(), values);
+            }
+            for text_box in &text_boxes {
+                fake.set_value(*text_box, "");
+            }
+        });
+        // Client4 shows prices with thousands separators and without a leading zero
+        for (entered, shown) in [("1299.00", "1,299.00"), ("0.20", ".20")] {
+            fake.on_keys(
+                &format!("{{Delete}}{entered}{{Enter}}"),
+                move |fake, element, _| fake.set_value(element, shown),
+            );
+        }
+        // Client4's popups are modal, so nothing may be typed into the screen while one is open
+        let typed_behind_popup = Rc::new(Cell::new(false));
+        let behind = typed_behind_popup.clone();
+        fake.on_keys("", move |fake, element, _| {
+            let is_text_box = fake.classname(&element).unwrap() == "ThunderRT6TextBox";
+            if is_text_box && fake.find_popup(None, "", 0).unwrap().is_some() {
+                behind.set(true);
+            }
+        });
+        let items = saved.clone();
+        fake.on_keys("{Enter}", move |fake, element, keys| {
+            // Entering a sku looks it up, whether or not the record on screen is a new one
+            if element == sku_box {
+                let sku = fake.value(&element).unwrap();
+                match items.borrow().get(&sku) {
+                    Some(values) => {
+                        for (index, value) in values.iter().enumerate() {
+                            fake.set_text_box(screen, index, value);
+                        }
+                    }
+                    None => {
+                        fake.add_popup(NOT_FOUND);
+                    }
+                }
+            }
+            // Pressing Enter on a new UPC asks whether to add it to the item
+            let upc = &fake.text_box_values(&screen).unwrap()[38];
+            if keys == "{Enter}" && !upc.is_empty() {
+                fake.add_popup(ADD_TO_UPC);
+            }
+        });
+
+        let session = AbcSession::with_window(fake, abc_window)
+            .with_timing(Timing::default().with_scale(0.1));
+        let inventory_window = InventoryScreen::new(&session.backend, screen).unwrap();
+        let record = ItemRecord {
+            sku: "NEWSKU ".to_string(),
+            desc: "3/4 {HEX} BOLT".to_string(),
+            upc: " 012345678905".to_string(),
+            weight: Some("0.5".parse().unwrap()),
+            list: Some("1,299.00".parse().unwrap()),
+            cost: Some(".20".parse().unwrap()),
+            group: Some("HW".parse().unwrap()),
+            sale_gl: Some(4000),
+            ..ItemRecord::default()
+        };
+        let created = session.create_item(&inventory_window, &record).unwrap();
+        assert_eq!(
+            created,
+            ItemRecord {
+                sku: "NEWSKU".to_string(),
+                upc: "012345678905".to_string(),
+                weight: Some("0.50".parse().unwrap()),
+                ..record.clone()
+            }
+        );
+        assert!(saved.borrow().contains_key("NEWSKU"));
+        assert!(!typed_behind_popup.get());
+        let sent = session.backend.sent_keys();
+        assert!(sent.iter().any(|(_, keys)| keys == "y"));
+
+        match session.create_item(&inventory_window, &record) {
+            Err(AbcError::AlreadyExists { record }) => assert_eq!(record, "NEWSKU"),
+            other => panic!("expected AlreadyExists, got {other:?}"),
+        }
+
+        // An item without a description exists all the same
+        let mut blank = vec![String::new(); 44];
+        blank[0] = "BLANK".to_string();
+        saved.borrow_mut().insert("BLANK".to_string(), blank);
+        let record = ItemRecord {
+            sku: "BLANK".to_string(),
+            desc: "NOW WITH A DESCRIPTION".to_string(),
+            ..ItemRecord::default()
+        };
+        assert!(matches!(
+            session.create_item(&inventory_window, &record),
+            Err(AbcError::AlreadyExists { .. })
+        ));
+    }
 }
@@ -0,0 +1,148 @@
+use std::fmt;
+
+use zeroize::Zeroizing;
+
+/// A key that is sent by name rather than by the character it types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Enter,
+    Esc,
+    Tab,
+    Delete,
+    Home,
+    End,
+    Up,
+    Down,
+    Left,
+    Right,
+    /// A function key, eg. `F(10)` for F10
+    F(u8),
+    Ctrl,
+    Alt,
+    Shift,
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::F(n) => write!(f, "{{F{n}}}"),
+            key => write!(f, "{{{key:?}}}"),
+        }
+    }
+}
+
+/// A keystroke sequence in the syntax of `UIElement::send_keys`, built so that text from records,
+/// such as a description containing `{` or `(`, is typed as is instead of being read as commands
+///
+/// ```
+/// use abc_uiautomation::keys::{Key, Keys};
+///
+/// let keys = Keys::new().key(Key::Delete).text("3/4 {HEX} BOLT").key(Key::Enter);
+/// assert_eq!(keys.as_str(), "{Delete}3/4 {{}HEX{}} BOLT{Enter}");
+/// ```
+///
+/// The keys are wiped from memory when they are dropped, since they may hold a password
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Keys(Zeroizing<String>);
+
+impl Keys {
+    /// An empty sequence
+    pub fn new() -> Self {
+        Keys::default()
+    }
+
+    /// An empty sequence with room for `capacity` bytes. Reserve enough room for secrets up front
+    /// so that growing the sequence never leaves a copy of them behind
+    pub fn with_capacity(capacity: usize) -> Self {
+        Keys(Zeroizing::new(String::with_capacity(capacity)))
+    }
+
+    /// Keys that are already in `send_keys` syntax, such as the answers of a
+    /// [`crate::PopupRule`]. Nothing is escaped
+    pub(crate) fn raw(keys: &str) -> Self {
+        Keys(Zeroizing::new(keys.to_string()))
+    }
+
+    /// Type `text` exactly as written. Braces and parentheses, which `send_keys` would otherwise
+    /// read as named keys and groups, are escaped
+    pub fn text(mut self, text: &str) -> Self {
+        for c in text.chars() {
+            match c {
+                '{' | '}' | '(' | ')' => {
+                    self.0.push('{');
+                    self.0.push(c);
+                    self.0.push('}');
+                }
+                c => self.0.push(c),
+            }
+        }
+        self
+    }
+
+    /// Press `key`
+    pub fn key(self, key: Key) -> Self {
+        self.repeat(key, 1)
+    }
+
+    /// Press `key` `times` times in a row
+    pub fn repeat(mut self, key: Key, times: usize) -> Self {
+        for _ in 0..times {
+            self.0.push_str(&key.to_string());
+        }
+        self
+    }
+
+    /// Hold down `modifier`, eg. [`Key::Ctrl`], while typing `keys`
+    pub fn hold(mut self, modifier: Key, keys: Keys) -> Self {
+        self.0.push_str(&modifier.to_string());
+        self.0.push('(');
+        self.0.push_str(keys.as_str());
+        self.0.push(')');
+        self
+    }
+
+    /// The sequence in `send_keys` syntax
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Keys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Key, Keys};
+    use crate::backend::FakeBackend;
+    use crate::AbcSession;
+
+    #[test]
+    fn test_keys() {
+        let keys = Keys::new()
+            .key(Key::F(10))
+            .text("7")
+            .repeat(Key::Enter, 2)
+            .text("WIDGET (BLUE) {NEW}")
+            .hold(Key::Ctrl, Keys::new().text("n"));
+        assert_eq!(
+            keys.as_str(),
+            "{F10}7{Enter}{Enter}WIDGET {(}BLUE{)} {{}NEW{}}{Ctrl}(n)"
+        );
+
+        // Text from a record ends up in the text box exactly as written
+        let fake = FakeBackend::new();
+        let screen = fake.add_screen("Inventory - Items (I)");
+        fake.add_text_boxes(screen, &["OLD"]);
+        let session = AbcSession::new(fake);
+        session
+            .set_text_box_value(&screen, 0, "3/4 {HEX} BOLT (ZINC)")
+            .unwrap();
+        assert_eq!(
+            session.read_text_box_value(&screen, 0).unwrap(),
+            "3/4 {HEX} BOLT (ZINC)"
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::abc_window::ABC_WINDOW_TITLE;
+use crate::field_map::load_config;
+use crate::{AbcError, AbcSession, Backend, Credentials, Result, MINUTES};
+#[cfg(windows)]
+use crate::{UIElement, UiaBackend};
+
+fn default_startup_timeout_ms() -> u64 {
+    MINUTES
+}
+
+/// How to start Client4 with [`launch_abc`]
+///
+/// ```toml
+/// executable = 'C:\ABC Software\Client4\Client4.exe'
+/// args = ["/store=1"]
+/// startup_timeout_ms = 90000
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchConfig {
+    /// Path to the Client4 executable
+    pub executable: PathBuf,
+    /// Command line arguments to start Client4 with
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Directory to start Client4 in. Defaults to the directory of the executable
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    /// How long to wait for the Client4 window to appear. This is the default delay of the
+    /// `launch_abc` operation, so it is scaled and can be overridden through [`crate::Timing`]
+    #[serde(default = "default_startup_timeout_ms")]
+    pub startup_timeout_ms: u64,
+}
+
+impl LaunchConfig {
+    /// Start `executable` without arguments, waiting up to a minute for it
+    pub fn new(executable: impl AsRef<Path>) -> Self {
+        LaunchConfig {
+            executable: executable.as_ref().to_path_buf(),
+            args: Vec::new(),
+            working_dir: None,
+            startup_timeout_ms: default_startup_timeout_ms(),
+        }
+    }
+
+    /// Start Client4 with `args`, returning the updated config
+    pub fn with_args(mut self, args: &[&str]) -> Self {
+        self.args = args.iter().map(|arg| arg.to_string()).collect();
+        self
+    }
+
+    /// Start Client4 in `working_dir`, returning the updated config
+    pub fn with_working_dir(mut self, working_dir: impl AsRef<Path>) -> Self {
+        self.working_dir = Some(working_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Wait up to `startup_timeout_ms` for the Client4 window, returning the updated config
+    pub fn with_startup_timeout(mut self, startup_timeout_ms: u64) -> Self {
+        self.startup_timeout_ms = startup_timeout_ms;
+        self
+    }
+
+    /// Parse a launch config in TOML format
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Config)` if `toml` cannot be parsed
+    pub fn from_toml_str(toml: &str) -> Result<Self> {
+        toml::from_str(toml).map_err(|e| AbcError::Config(e.to_string()))
+    }
+
+    /// Parse a launch config in JSON format. See [`LaunchConfig::from_toml_str`]
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Config)` if `json` cannot be parsed
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| AbcError::Config(e.to_string()))
+    }
+
+    /// Read a launch config from a `.toml` or `.json` file
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Config)` if the file cannot be read or parsed, or if its extension
+    /// is neither `toml` nor `json`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        load_config(
+            path.as_ref(),
+            LaunchConfig::from_toml_str,
+            LaunchConfig::from_json_str,
+        )
+    }
+}
+
+/// Start Client4 and wait until its main window appears and answers UI Automation, so jobs can
+/// run unattended even when nobody opened Client4 after a reboot
+///
+/// # Arguments
+///
+/// * `config` - Where Client4 is and how to start it
+/// * `credentials` - If given, log in once Client4 is up. See [`crate::ensure_logged_in`]
+///
+/// # Returns
+///
+/// The new Client4 window
+///
+/// # Errors
+///
+/// Return `Err(AbcError::Launch)` if the executable does not exist, cannot be started, or exits
+/// with an error before its window appears. Return `Err(AbcError::Timeout)` if the window does
+/// not appear in time
+#[cfg(windows)]
+pub fn launch_abc(
+    config: &LaunchConfig,
+    credentials: Option<&dyn Credentials>,
+) -> Result<UIElement> {
+    let mut session = AbcSession::new(UiaBackend::new()?);
+    Ok(session.launch_abc(config, credentials)?.clone())
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Same as [`launch_abc`]. The session drives the new Client4 window afterwards
+    pub fn launch_abc(
+        &mut self,
+        config: &LaunchConfig,
+        credentials: Option<&dyn Credentials>,
+    ) -> Result<&B::Element> {
+        if !config.executable.is_file() {
+            return Err(AbcError::Launch(format!(
+                "{} does not exist",
+                config.executable.display()
+            )));
+        }
+        let working_dir = match &config.working_dir {
+            Some(working_dir) => working_dir.as_path(),
+            None => config.executable.parent().unwrap_or(Path::new(".")),
+        };
+
+        // Client4 may be started through a launcher that hands off to another process, so any
+        // Client4 window that was not open before counts as well as one of the child process
+        let existing = self.backend.find_windows(ABC_WINDOW_TITLE)?;
+        let mut child = Command::new(&config.executable)
+            .args(&config.args)
+            .current_dir(working_dir)
+            .spawn()
+            .map_err(|e| {
+                AbcError::Launch(format!(
+                    "could not start {}: {e}",
+                    config.executable.display()
+                ))
+            })?;
+        let child_id = child.id();
+
+        let mut window = None;
+        self.wait_for(
+            "launch_abc",
+            config.startup_timeout_ms,
+            "the Client4 window to appear",
+            || {
+                for candidate in self.backend.find_windows(ABC_WINDOW_TITLE)? {
+                    let is_new = self.backend.process_id(&candidate)? == child_id
+                        || !existing.iter().any(|e| self.is_same_window(e, &candidate));
+                    // The window is only responsive once it answers a query for its contents
+                    if is_new && self.backend.children(&candidate).is_ok() {
+                        window = Some(candidate);
+                        return Ok(true);
+                    }
+                }
+                match child.try_wait() {
+                    Ok(Some(status)) if !status.success() => Err(AbcError::Launch(format!(
+                        "{} exited with {status} before its window appeared",
+                        config.executable.display()
+                    ))),
+                    _ => Ok(false),
+                }
+            },
+        )?;
+
+        self.use_window(window);
+        self.user = None;
+        if let Some(credentials) = credentials {
+            self.ensure_logged_in(&credentials)?;
+        }
+        self.abc_window()
+    }
+
+    fn is_same_window(&self, a: &B::Element, b: &B::Element) -> bool {
+        let id = |window| -> Option<(u32, String)> {
+            Some((
+                self.backend.process_id(window).ok()?,
+                self.backend.name(window).ok()?,
+            ))
+        };
+        id(a) == id(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LaunchConfig;
+    use crate::backend::FakeBackend;
+    use crate::{AbcError, AbcSession};
+
+    #[test]
+    fn test_launch_errors() {
+        let mut session = AbcSession::new(FakeBackend::new());
+        let missing = LaunchConfig::new("C:\\ABC Software\\Client4\\missing.exe");
+        match session.launch_abc(&missing, None) {
+            Err(AbcError::Launch(message)) => assert!(message.contains("missing.exe")),
+            other => panic!("expected Launch, got {other:?}"),
+        }
+
+        // The test binary exits right away without ever opening a Client4 window
+        let exits = LaunchConfig::new(std::env::current_exe().unwrap())
+            .with_args(&["--list"])
+            .with_startup_timeout(300);
+        match session.launch_abc(&exits, None) {
+            Err(AbcError::Timeout { waiting_for }) => assert!(waiting_for.contains("Client4")),
+            other => panic!("expected Timeout, got {other:?}"),
+        }
+    }
+}
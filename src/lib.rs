@@ -1,14 +1,72 @@
+pub mod abc_window;
 pub mod accounts_receivable;
+pub mod backend;
+pub mod credentials;
 pub mod customer_file;
+pub mod discovery;
+pub mod error;
+pub mod field_map;
+pub mod gtin;
 pub mod inventory;
+pub mod keys;
+pub mod launch;
+pub mod money;
+pub mod popups;
 pub mod reports;
+pub mod retry;
+pub mod screens;
+pub mod session;
+pub mod snapshot;
+pub mod timing;
+mod trace;
 
 use std::path::PathBuf;
 use std::time::Duration;
 use std::{thread, time};
-use uiautomation::{UIAutomation, UIMatcher, UITreeWalker};
-
+use tracing::field::Empty;
+use tracing::{debug, debug_span, info_span};
+#[cfg(windows)]
+use uiautomation::UITreeWalker;
+
+use popups::{ERROR, SAVE_CHANGES};
+use screens::{RECORD_SCREEN_TITLES, SCREEN_TITLES};
+use trace::{traced, REDACTED};
+
+#[cfg(windows)]
+pub use abc_window::{find_abc_window, list_abc_windows};
+pub use abc_window::{AbcWindow, WindowSelector};
+#[cfg(windows)]
+pub use backend::UiaBackend;
+pub use backend::{Backend, BackendError, Bounds};
+pub use credentials::{
+    Credentials, EncryptedFileCredentials, EnvCredentials, FileCredentials, LoginCredentials,
+    Password,
+};
+#[cfg(windows)]
+pub use discovery::discover_fields;
+pub use discovery::{draft_field_map, DiscoveredField};
+pub use error::{AbcError, Result};
+pub use field_map::{field_maps, set_field_maps, FieldMap, FieldMaps};
+pub use gtin::Gtin;
+pub use keys::{Key, Keys};
+#[cfg(windows)]
+pub use launch::launch_abc;
+pub use launch::LaunchConfig;
+pub use money::Money;
+pub use popups::{PopupInfo, PopupPolicy, PopupRegistry, PopupRule, SeenPopup};
+pub use retry::{Comparison, RetryPolicy};
+pub use screens::{
+    CustomerScreen, InventoryScreen, InvoicesScreen, LoginScreen, ReportMenuScreen,
+    ReportOptionsScreen, SpecialReportsScreen,
+};
+pub use session::AbcSession;
+#[cfg(windows)]
+pub use snapshot::snapshot_element;
+pub use snapshot::{ElementSnapshot, SnapshotChange};
+pub use timing::{set_timing, timing, Timing};
+#[cfg(windows)]
 pub use uiautomation::Error;
+#[cfg(windows)]
 pub use uiautomation::UIElement;
 
 pub const MILLIS: u64 = 1;
@@ -16,27 +74,8 @@ pub const SECONDS: u64 = 1000 * MILLIS;
 pub const MINUTES: u64 = 60 * SECONDS;
 pub const SHORT_WAIT_MS: u64 = 80 * MILLIS;
 
-/// Convenience function that wraps `UIAutomation.create_matcher()`. Sets `from` to the root
-/// element, and sets the `timeout` to `SHORT_WAIT_MS * 30`
-///
-/// # Arguments
-///
-/// * `automation` - Reference to the `UIAutomation` struct to create the matcher on
-///
-/// # Returns
-///
-/// If successful, return the `UIMatcher`. Will return `Err(uiautomation::Error)` if the root
-/// element cannot be found
-///
-/// # Errors
-///
-/// If the root element cannot be found, return `Err(uiautomation::Error)`
-fn create_matcher_wrapper(automation: &UIAutomation) -> uiautomation::Result<UIMatcher> {
-    Ok(automation
-        .create_matcher()
-        .from(automation.get_root_element()?)
-        .timeout(SHORT_WAIT_MS * 30))
-}
+/// How many screens [`return_to_main_menu`] closes before giving up
+const MAX_SCREENS_TO_CLOSE: usize = 10;
 
 /// Convenience wrapper around `std::thread::sleep` that pauses the thread for a
 /// given number of milliseconds
@@ -48,21 +87,55 @@ pub fn wait(duration_ms: u64) {
     thread::sleep(time::Duration::from_millis(duration_ms));
 }
 
-/// Attempt to find and return the active ABC Client4 window
+/// Poll `condition` every `interval_ms` until it returns `Ok(true)`, giving up after
+/// `timeout_ms`. Use this instead of [`wait`] whenever there is something on screen that shows
+/// Client4 has caught up
+///
+/// # Arguments
+///
+/// * `timeout_ms` - How long to keep polling before giving up
+/// * `interval_ms` - How long to sleep between polls
+/// * `waiting_for` - Description of the condition, used in the timeout error
+/// * `condition` - Check that returns `Ok(true)` once Client4 is in the expected state
+///
+/// # Errors
+///
+/// Return `Err(AbcError::Timeout)` if `condition` is still `false` after `timeout_ms`, or
+/// forward the error if `condition` fails
+pub fn wait_until(
+    timeout_ms: u64,
+    interval_ms: u64,
+    waiting_for: &str,
+    mut condition: impl FnMut() -> Result<bool>,
+) -> Result<()> {
+    let start = time::Instant::now();
+    loop {
+        if condition()? {
+            return Ok(());
+        }
+        if start.elapsed() >= Duration::from_millis(timeout_ms) {
+            return Err(AbcError::Timeout {
+                waiting_for: waiting_for.to_string(),
+            });
+        }
+        wait(interval_ms);
+    }
+}
+
+/// Attempt to find and return the active ABC Client4 window. With several Client4 instances open,
+/// this is the first one found; use [`find_abc_window`] to pick a specific one
 ///
 /// # Returns
 ///
 /// Will return the ABC Client4 `UIElement` if successful. If Client4 is not already open, return
-/// `uiautomation::Error`
+/// [`AbcError`]
 ///
 /// # Errors
 ///
-/// Will return `Err(uiautomation::Error)` if the Client4 window cannot be found
-pub fn ensure_abc() -> uiautomation::Result<UIElement> {
-    let automation = UIAutomation::new()?;
-    create_matcher_wrapper(&automation)?
-        .contains_name("ABC Accounting Client")
-        .find_first()
+/// Will return `Err(AbcError::Automation)` if the Client4 window cannot be found
+#[cfg(windows)]
+pub fn ensure_abc() -> Result<UIElement> {
+    Ok(AbcSession::connect()?.abc_window()?.clone())
 }
 
 /// Print the tree of elements starting with the first instace of `element` to the last branch
@@ -73,12 +146,13 @@ pub fn ensure_abc() -> uiautomation::Result<UIElement> {
 ///
 /// # Returns
 ///
-/// If successful, return unit type. If a failure occurs, return [`uiautomation::Error`]
+/// If successful, return unit type. If a failure occurs, return [`AbcError`]
 ///
 /// # Errors
 ///
-/// Will return [`uiautomation::Error`] if an element cannot be found
-pub fn print_element(element: &UIElement) -> uiautomation::Result<()> {
+/// Will return [`AbcError::Automation`] if an element cannot be found
+#[cfg(windows)]
+pub fn print_element(element: &UIElement) -> Result<()> {
     print_element_helper(
         &uiautomation::UIAutomation::new()?.get_control_view_walker()?,
         element,
@@ -86,11 +160,8 @@ pub fn print_element(element: &UIElement) -> uiautomation::Result<()> {
     )
 }
 
-fn print_element_helper(
-    walker: &UITreeWalker,
-    element: &UIElement,
-    level: usize,
-) -> uiautomation::Result<()> {
+#[cfg(windows)]
+fn print_element_helper(walker: &UITreeWalker, element: &UIElement, level: usize) -> Result<()> {
     for _ in 0..level {
         print!(" ")
     }
@@ -128,37 +199,34 @@ fn print_element_helper(
 /// # Returns
 ///
 /// Will return `Ok(())` if the function runs successfully. Otherwise, return
-/// `Err(uiautomation::Error)` if keypresses fail to send of if the root element cannot be found
+/// `Err(AbcError)` if keypresses fail to send of if the root element cannot be found
 ///
 /// # Errors
 ///
-/// Will return `uiautomation::Error` if any keypresses fail to send or if the root element cannot
+/// Will return `AbcError::Automation` if any keypresses fail to send or if the root element cannot
 /// be found
-pub fn send_ctrl_n(abc_window: &UIElement, save_changes: bool) -> uiautomation::Result<()> {
-    let automation = UIAutomation::new()?;
-
-    abc_window.hold_send_keys("{Ctrl}", "N", SHORT_WAIT_MS)?;
-    wait(SHORT_WAIT_MS);
-
-    // Detect if the "Save changes before proceeding" screen pops up. If it does,
-    // perform the appropriate action to either save or discard changes depending on the value of
-    // `save_changes`
-    let save_changes_popup_result = automation
-        .create_matcher()
-        .from(automation.get_root_element()?)
-        .timeout(SHORT_WAIT_MS / 2)
-        .name("Save changes before proceeding?")
-        .find_first();
-    match (save_changes_popup_result, save_changes) {
-        (Ok(popup), true) => {
-            popup.send_keys("{enter}", SHORT_WAIT_MS)?;
-        }
-        (Ok(popup), false) => {
-            popup.send_keys("{right}{enter}", SHORT_WAIT_MS)?;
-        }
-        _ => (), // The popup cannot be found, so there are no changes to save
-    }
-    Ok(())
+#[cfg(windows)]
+pub fn send_ctrl_n(abc_window: &UIElement, save_changes: bool) -> Result<()> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).send_ctrl_n(save_changes)
+}
+
+/// Bring Client4 back to its main menu, eg. after a job failed halfway through an edit. Screens
+/// are closed with Escape one at a time, every popup in the session's [`PopupRegistry`] is
+/// dismissed (even those that would otherwise fail an operation), and unsaved changes on record
+/// screens are saved or discarded through [`send_ctrl_n`]
+///
+/// # Arguments
+///
+/// * `abc_window` - Reference to the Client4 applicaton `UIElement`
+/// * `discard_changes` - `true` to throw away unsaved changes, `false` to save them
+///
+/// # Errors
+///
+/// Return `Err(AbcError::WrongScreen)` if a screen is still open afterwards
+#[cfg(windows)]
+pub fn return_to_main_menu(abc_window: &UIElement, discard_changes: bool) -> Result<()> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone())
+        .return_to_main_menu(discard_changes)
 }
 
 /// Attempt to read the value of any RT6ThunderTextBox on a given ABC Client4 screen
@@ -175,29 +243,11 @@ pub fn send_ctrl_n(abc_window: &UIElement, save_changes: bool) -> uiautomation::
 ///
 /// # Errors
 ///
-/// Return `Err(uiautomation::Error)` if the textbox cannot be found or its value fails to convert
-/// to string for some reason
-pub fn read_text_box_value(screen: &UIElement, box_index: usize) -> uiautomation::Result<String> {
-    let automation = UIAutomation::new()?;
-
-    let all_text_boxes = create_matcher_wrapper(&automation)?
-        .from(screen.to_owned())
-        .classname("ThunderRT6TextBox")
-        .find_all()?;
-
-    let desired_txtbx = match all_text_boxes.get(box_index) {
-        Some(b) => b,
-        None => {
-            return Err(uiautomation::Error::new(
-                2,
-                &format!("No textbox found at index {}", box_index),
-            ))
-        }
-    };
-
-    Ok(desired_txtbx
-        .get_property_value(uiautomation::types::UIProperty::ValueValue)?
-        .get_string()?)
+/// Return `Err(AbcError::FieldNotFound)` if the textbox cannot be found, or
+/// `Err(AbcError::Automation)` if its value fails to convert to string for some reason
+#[cfg(windows)]
+pub fn read_text_box_value(screen: &UIElement, box_index: usize) -> Result<String> {
+    AbcSession::new(UiaBackend::new()?).read_text_box_value(screen, box_index)
 }
 
 /// Controls Client4 to enter text into a text input field
@@ -208,63 +258,26 @@ pub fn read_text_box_value(screen: &UIElement, box_index: usize) -> uiautomation
 /// * `value` - What to enter into the text box
 ///
 /// # Errors
-/// Forwards any of the following automation errors:
+/// Returns [`AbcError::FieldNotFound`] if there is no text input at the index listed. Forwards
+/// any of the following automation errors:
 /// * Failing to create a new instance of the controller
-/// * Failing to Find a text input at the index listed
 /// * Failing to send input to the specified text box
+#[cfg(windows)]
 pub fn set_text_box_value(
     screen: &UIElement,
     box_index: usize,
     value: impl ToString,
-) -> uiautomation::Result<()> {
-    let automation = UIAutomation::new()?;
-    let all_text_boxes = create_matcher_wrapper(&automation)?
-        .from(screen.to_owned())
-        .classname("ThunderRT6TextBox")
-        .find_all()?;
-
-    let desired_txtbx = match all_text_boxes.get(box_index) {
-        Some(b) => b,
-        None => {
-            return Err(uiautomation::Error::new(
-                2,
-                &format!("No textbox found at index {}", box_index),
-            ))
-        }
-    };
-    desired_txtbx.click()?;
-    desired_txtbx.send_keys(
-        &format!("{{Delete}}{}{{Enter}}", value.to_string()),
-        SHORT_WAIT_MS,
-    )?;
-
-    Ok(())
+) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).set_text_box_value(screen, box_index, value)
 }
 
+#[cfg(windows)]
 pub fn set_text_box_value_no_enter(
     screen: &UIElement,
     box_index: usize,
     value: impl ToString,
-) -> uiautomation::Result<()> {
-    let automation = UIAutomation::new()?;
-    let all_text_boxes = create_matcher_wrapper(&automation)?
-        .from(screen.to_owned())
-        .classname("ThunderRT6TextBox")
-        .find_all()?;
-
-    let desired_txtbx = match all_text_boxes.get(box_index) {
-        Some(b) => b,
-        None => {
-            return Err(uiautomation::Error::new(
-                2,
-                &format!("No textbox found at index {}", box_index),
-            ))
-        }
-    };
-    desired_txtbx.click()?;
-    desired_txtbx.send_keys(&format!("{{Delete}}{}", value.to_string()), SHORT_WAIT_MS)?;
-
-    Ok(())
+) -> Result<()> {
+    AbcSession::new(UiaBackend::new()?).set_text_box_value_no_enter(screen, box_index, value)
 }
 
 pub fn data_file_is_ready(path: &PathBuf) -> Result<bool, std::io::Error> {
@@ -305,100 +318,537 @@ pub fn await_data_file_ready(
     ))
 }
 
-pub fn find_popup(abc_window: &UIElement) -> uiautomation::Result<Option<UIElement>> {
-    let automation = UIAutomation::new()?;
-    Ok(automation
-        .create_matcher()
-        .timeout(1 * SECONDS)
-        .from(abc_window.to_owned())
-        .classname("ThunderRT6FormDC")
-        .contains_name("Error")
-        .find_first()
-        .ok())
+#[cfg(windows)]
+pub fn find_popup(abc_window: &UIElement) -> Result<Option<UIElement>> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).find_popup()
+}
+
+/// Read the title, message, and buttons of a popup, eg. one found with [`find_popup`]
+#[cfg(windows)]
+pub fn popup_info(popup: &UIElement) -> Result<PopupInfo> {
+    AbcSession::new(UiaBackend::new()?).popup_info(popup)
+}
+
+#[cfg(windows)]
+pub fn load_login_screen(abc_window: &UIElement) -> Result<LoginScreen> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).load_login_screen()
 }
 
-pub fn load_login_screen(abc_window: &UIElement) -> uiautomation::Result<UIElement> {
-    let automation = UIAutomation::new()?;
+#[cfg(windows)]
+pub fn login(abc_window: &UIElement, username: &str, password: &str) -> Result<()> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).login(username, password)
+}
 
-    let match_name = "Utilities - System Date and Time";
-    abc_window.send_keys("{F10}*", SHORT_WAIT_MS * 3)?;
-    create_matcher_wrapper(&automation)?
-        .contains_name(match_name)
-        .find_first()
+/// Read the user that is logged into Client4 from the username box of the "Utilities - System
+/// Date and Time" screen, which is opened with [`load_login_screen`] and left open
+///
+/// # Returns
+///
+/// `Ok(None)` if nobody is logged in
+///
+/// # Errors
+///
+/// Return `Err(AbcError::Automation)` if the login screen cannot be opened or read
+#[cfg(windows)]
+pub fn current_user(abc_window: &UIElement) -> Result<Option<String>> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).current_user()
+}
+
+/// Check whether anyone is logged into Client4. See [`current_user`]
+#[cfg(windows)]
+pub fn is_logged_in(abc_window: &UIElement) -> Result<bool> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).is_logged_in()
+}
+
+/// Make sure the user from `credentials` is logged into Client4, logging in only if they are not
+/// already. This is safe to call at the start of every job. See [`current_user`] and
+/// [`login_with`]
+///
+/// # Errors
+///
+/// Same as [`login_with`]
+#[cfg(windows)]
+pub fn ensure_logged_in(abc_window: &UIElement, credentials: &impl Credentials) -> Result<()> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).ensure_logged_in(credentials)
+}
+
+/// Log the current user out of Client4. Client4 has no command to log out; the user in the
+/// username box of the login screen opened with [`load_login_screen`] is who is logged in, so
+/// clearing the box and pressing Enter leaves nobody logged in, the same as a fresh start of
+/// Client4. The box is read back afterwards to make sure Client4 took the change, and the login
+/// screen is left open. Does nothing if nobody is logged in
+///
+/// # Errors
+///
+/// Return `Err(AbcError::VerificationFailed)` if the username box still holds a user afterwards,
+/// or `Err(AbcError::UnexpectedPopup)` if Client4 refuses to log out
+#[cfg(windows)]
+pub fn logout(abc_window: &UIElement) -> Result<()> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).logout()
 }
 
-fn verify_login(
+/// Log into Client4 as the user from `credentials` in place of whoever is logged in now, eg. to
+/// run a job under a service account
+///
+/// # Returns
+///
+/// The user that was logged in before, if any, so their session can be restored afterwards
+///
+/// # Errors
+///
+/// Same as [`login_with`]. `Err(AbcError::VerificationFailed)` means the username box did not
+/// change to the new user
+#[cfg(windows)]
+pub fn switch_user(
     abc_window: &UIElement,
-    login_screen: &UIElement,
-    username: &str,
-) -> uiautomation::Result<()> {
-    let login_screen_name_is_correct = login_screen
-        .get_name()?
-        .starts_with("Utilities - System Date and Time");
-    if !login_screen_name_is_correct {
-        return Err(uiautomation::Error::new(
-            2,
-            "Supplied incorrect uielement for ABC login screen",
-        ));
+    credentials: &impl Credentials,
+) -> Result<Option<String>> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).switch_user(credentials)
+}
+
+/// Log into Client4 with credentials from `credentials`, eg. [`EnvCredentials`], instead of
+/// passing the password around as a plain string. See [`login`]
+///
+/// # Errors
+///
+/// Return `Err(AbcError::Credentials)` if the credentials cannot be loaded, and otherwise the
+/// same errors as [`login`]. No error contains the password
+#[cfg(windows)]
+pub fn login_with(abc_window: &UIElement, credentials: &impl Credentials) -> Result<()> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).login_with(credentials)
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Same as [`send_ctrl_n`]
+    pub fn send_ctrl_n(&self, save_changes: bool) -> Result<()> {
+        traced(debug_span!("send_ctrl_n", save_changes), || {
+            let abc_window = self.abc_window()?;
+            self.hold_send_keys(
+                abc_window,
+                Key::Ctrl,
+                &Keys::new().text("N"),
+                self.timing.short(),
+            )?;
+            wait(self.timing.delay("send_ctrl_n", self.timing.short_wait_ms));
+
+            // Detect if the "Save changes before proceeding" screen pops up. If it does,
+            // perform the appropriate action to either save or discard changes depending on the
+            // value of `save_changes`
+            let policy = match save_changes {
+                true => PopupPolicy::Accept,
+                false => PopupPolicy::Decline,
+            };
+            if self.wait_for_popup(
+                "save_changes_popup",
+                self.timing.short_wait_ms / 2,
+                SAVE_CHANGES,
+            )? {
+                self.handle_popups_with(&self.popups.clone().with_policy(SAVE_CHANGES, policy))?;
+            }
+            Ok(())
+        })
     }
 
-    let abc_window_named_correctly = abc_window.get_name()?.contains("ABC Accounting Client");
-    if !abc_window_named_correctly {
-        return Err(uiautomation::Error::new(
-            2,
-            "Supplied incorrect uielement for ABC window",
-        ));
+    /// Same as [`return_to_main_menu`]
+    pub fn return_to_main_menu(&self, discard_changes: bool) -> Result<()> {
+        traced(info_span!("return_to_main_menu", discard_changes), || {
+            let abc_window = self.abc_window()?;
+            let mut popups = self.popups.clone().with_policy(
+                SAVE_CHANGES,
+                match discard_changes {
+                    true => PopupPolicy::Decline,
+                    false => PopupPolicy::Accept,
+                },
+            );
+            let failing: Vec<String> = popups
+                .rules()
+                .iter()
+                .filter(|rule| rule.policy == PopupPolicy::Fail)
+                .map(|rule| rule.title.clone())
+                .collect();
+            for title in failing {
+                popups = popups.with_policy(&title, PopupPolicy::Collect);
+            }
+
+            for _ in 0..MAX_SCREENS_TO_CLOSE {
+                self.handle_popups_with(&popups)?;
+                let Some(screen) = self.open_screen()? else {
+                    return Ok(());
+                };
+                if RECORD_SCREEN_TITLES.contains(&screen) {
+                    match self.send_ctrl_n(!discard_changes) {
+                        // The popup was dismissed, and the next round takes care of anything else
+                        Ok(()) | Err(AbcError::UnexpectedPopup { .. }) => (),
+                        Err(e) => return Err(e),
+                    }
+                }
+                self.send_keys(abc_window, &Keys::new().key(Key::Esc), self.timing.short())?;
+                // Client4 may ask about the screen before closing it, which the next round answers
+                match self.wait_for(
+                    "return_to_main_menu",
+                    self.timing.short_wait_ms * 10,
+                    &format!("the '{screen}' screen to close"),
+                    || Ok(!self.screen_is_open(screen)?),
+                ) {
+                    Ok(()) | Err(AbcError::Timeout { .. }) => (),
+                    Err(e) => return Err(e),
+                }
+            }
+            match self.open_screen()? {
+                None => Ok(()),
+                Some(screen) => Err(AbcError::WrongScreen {
+                    expected: "the Client4 main menu".to_string(),
+                    actual: screen.to_string(),
+                }),
+            }
+        })
     }
 
-    let automation = UIAutomation::new()?;
-    let all_text_boxes = create_matcher_wrapper(&automation)?
-        .from(login_screen.clone())
-        .classname("ThunderRT6TextBox")
-        .find_all()?;
-    let only_three_text_boxes = all_text_boxes.len() == 3;
-    if !only_three_text_boxes {
-        return Err(uiautomation::Error::new(
-            2,
-            &format!(
-                "Expected login window to only have 3 text boxes, but found {}",
-                all_text_boxes.len()
-            ),
-        ));
+    /// The title of the first screen in [`SCREEN_TITLES`] that is open, if any
+    fn open_screen(&self) -> Result<Option<&'static str>> {
+        for &title in SCREEN_TITLES {
+            if self.screen_is_open(title)? {
+                return Ok(Some(title));
+            }
+        }
+        Ok(None)
     }
-    if let Some(_popup) = find_popup(abc_window)? {
-        abc_window.send_keys("{enter}", SHORT_WAIT_MS)?;
-        return Err(uiautomation::Error::new(
-            2,
-            "Encountered popup while logging into ABC. You are not logged in",
-        ));
+
+    /// Same as [`read_text_box_value`]
+    pub fn read_text_box_value(&self, screen: &B::Element, box_index: usize) -> Result<String> {
+        let span = debug_span!(
+            "read_text_box",
+            screen = Empty,
+            field = Empty,
+            index = box_index
+        );
+        let secret = !span.is_disabled() && self.record_text_box(&span, screen, box_index);
+        traced(span, || {
+            let value = self.backend.value(&self.text_box_at(screen, box_index)?)?;
+            debug!(value = if secret { REDACTED } else { &value }, "read");
+            Ok(value)
+        })
     }
-    let username_correct =
-        read_text_box_value(login_screen, 2)?.to_lowercase() == username.to_lowercase();
-    if !username_correct {
-        return Err(uiautomation::Error::new(
-            2,
-            "Username was not set correctly while logging into ABC",
-        ));
+
+    /// Find the ThunderRT6TextBox at `box_index` on `screen`
+    pub(crate) fn text_box_at(&self, screen: &B::Element, box_index: usize) -> Result<B::Element> {
+        match self.backend.text_boxes(screen)?.into_iter().nth(box_index) {
+            Some(b) => Ok(b),
+            None => Err(AbcError::FieldNotFound {
+                screen: self.backend.name(screen)?,
+                index: box_index,
+            }),
+        }
     }
-    Ok(())
-}
 
-pub fn login(abc_window: &UIElement, username: &str, password: &str) -> uiautomation::Result<()> {
-    let abc_window_named_correctly = abc_window.get_name()?.contains("ABC Accounting Client");
-    if !abc_window_named_correctly {
-        return Err(uiautomation::Error::new(
-            2,
-            "Supplied incorrect uielement for ABC window",
-        ));
+    /// Same as [`set_text_box_value`]
+    pub fn set_text_box_value(
+        &self,
+        screen: &B::Element,
+        box_index: usize,
+        value: impl ToString,
+    ) -> Result<()> {
+        self.enter_text_box_value(screen, box_index, &value.to_string(), Some(Key::Enter))
     }
 
-    let login_screen = load_login_screen(abc_window)?;
-    set_text_box_value(&login_screen, 2, username)?;
-    if let Some(popup) = find_popup(abc_window)? {
-        abc_window.send_keys("{enter}", SHORT_WAIT_MS)?;
-        return Err(uiautomation::Error::new(2, &format!("Encountered unexpected popup after entering username. Your username is probably incorrect {:?}", popup)));
+    /// Same as [`set_text_box_value_no_enter`]
+    pub fn set_text_box_value_no_enter(
+        &self,
+        screen: &B::Element,
+        box_index: usize,
+        value: impl ToString,
+    ) -> Result<()> {
+        self.enter_text_box_value(screen, box_index, &value.to_string(), None)
+    }
+
+    /// Replace the contents of the text box at `box_index` with `value`, followed by `end_key`.
+    /// The value is typed as is, and traced unless the field map names the text box as a secret,
+    /// eg. a password
+    fn enter_text_box_value(
+        &self,
+        screen: &B::Element,
+        box_index: usize,
+        value: &str,
+        end_key: Option<Key>,
+    ) -> Result<()> {
+        let span = debug_span!(
+            "set_text_box",
+            screen = Empty,
+            field = Empty,
+            index = box_index
+        );
+        let secret = self.record_text_box(&span, screen, box_index);
+        traced(span, || {
+            let desired_txtbx = self.text_box_at(screen, box_index)?;
+            self.backend.click(&desired_txtbx)?;
+            let keys = |value| {
+                let keys = Keys::new().key(Key::Delete).text(value);
+                match end_key {
+                    Some(end_key) => keys.key(end_key),
+                    None => keys,
+                }
+            };
+            let traced_value = if secret { REDACTED } else { value };
+            self.send_keys_as(
+                &desired_txtbx,
+                &keys(value),
+                &keys(traced_value),
+                self.timing.short(),
+            )
+        })
+    }
+
+    /// Same as [`find_popup`]
+    pub fn find_popup(&self) -> Result<Option<B::Element>> {
+        let abc_window = self.abc_window()?;
+        Ok(self.backend.find_popup(
+            Some(abc_window),
+            "Error",
+            self.timing.delay("error_popup", SECONDS),
+        )?)
+    }
+
+    /// Same as [`load_login_screen`]
+    pub fn load_login_screen(&self) -> Result<LoginScreen<B::Element>> {
+        let title = LoginScreen::<B::Element>::TITLE;
+        traced(debug_span!("load_screen", screen = title), || {
+            let abc_window = self.abc_window()?;
+            self.send_keys(
+                abc_window,
+                &Keys::new().key(Key::F(10)).text("*"),
+                self.timing.short() * 3,
+            )?;
+            LoginScreen::find(&self.backend)
+        })
+    }
+
+    /// Close the login screen opened with [`AbcSession::load_login_screen`] with Escape, and
+    /// wait for Client4 to take it down
+    fn close_login_screen(&self) -> Result<()> {
+        let title = LoginScreen::<B::Element>::TITLE;
+        traced(debug_span!("close_screen", screen = title), || {
+            self.send_keys(
+                self.abc_window()?,
+                &Keys::new().key(Key::Esc),
+                self.timing.short(),
+            )?;
+            self.wait_for(
+                "close_login_screen",
+                self.timing.short_wait_ms * 10,
+                "the login screen to close",
+                || Ok(!self.screen_is_open(title)?),
+            )
+        })
+    }
+
+    /// Same as [`login`]
+    pub fn login(&mut self, username: &str, password: &str) -> Result<()> {
+        self.login_as(
+            &LoginCredentials::new(username, Password::new(password)),
+            None,
+        )
+    }
+
+    /// Same as [`login_with`]
+    pub fn login_with(&mut self, credentials: &impl Credentials) -> Result<()> {
+        self.login_as(&credentials.credentials()?, None)
+    }
+
+    /// Same as [`current_user`]
+    pub fn current_user(&self) -> Result<Option<String>> {
+        self.read_user(&self.load_login_screen()?)
+    }
+
+    /// Read the user that is logged in from the username box of the open `login_screen`
+    fn read_user(&self, login_screen: &LoginScreen<B::Element>) -> Result<Option<String>> {
+        let username = self.read_text_box_value(
+            login_screen.element(),
+            self.field_maps.login.index("username")?,
+        )?;
+        let username = username.trim();
+        Ok((!username.is_empty()).then(|| username.to_string()))
+    }
+
+    /// Same as [`is_logged_in`]
+    pub fn is_logged_in(&self) -> Result<bool> {
+        Ok(self.current_user()?.is_some())
+    }
+
+    /// Same as [`ensure_logged_in`]
+    pub fn ensure_logged_in(&mut self, credentials: &impl Credentials) -> Result<()> {
+        let credentials = credentials.credentials()?;
+        let login_screen = self.load_login_screen()?;
+        match self.read_user(&login_screen)? {
+            Some(user) if user.eq_ignore_ascii_case(&credentials.username) => {
+                self.user = Some(credentials.username);
+                Ok(())
+            }
+            _ => self.login_as(&credentials, Some(login_screen)),
+        }
+    }
+
+    /// Same as [`logout`]
+    pub fn logout(&mut self) -> Result<()> {
+        let login_screen = self.load_login_screen()?;
+        let username_index = self.field_maps.login.index("username")?;
+        if self
+            .read_text_box_value(login_screen.element(), username_index)?
+            .trim()
+            .is_empty()
+        {
+            self.user = None;
+            return Ok(());
+        }
+        self.set_text_box_value(login_screen.element(), username_index, "")?;
+        self.handle_popups()?;
+        let username = self.read_text_box_value(login_screen.element(), username_index)?;
+        if !username.trim().is_empty() {
+            return Err(AbcError::VerificationFailed {
+                field: "username".to_string(),
+                expected: String::new(),
+                actual: username,
+            });
+        }
+        self.user = None;
+        Ok(())
+    }
+
+    /// Same as [`switch_user`]
+    pub fn switch_user(&mut self, credentials: &impl Credentials) -> Result<Option<String>> {
+        let credentials = credentials.credentials()?;
+        let login_screen = self.load_login_screen()?;
+        let previous = self.read_user(&login_screen)?;
+        match &previous {
+            Some(user) if user.eq_ignore_ascii_case(&credentials.username) => {
+                self.user = Some(credentials.username);
+            }
+            _ => self.login_as(&credentials, Some(login_screen))?,
+        }
+        Ok(previous)
+    }
+
+    /// Log in as the user from `credentials` on `login_screen`, or on a login screen opened with
+    /// [`AbcSession::load_login_screen`] if it is `None`. Passing the screen that was just read
+    /// from keeps Client4 from being sent F10-* a second time
+    fn login_as(
+        &mut self,
+        credentials: &LoginCredentials,
+        login_screen: Option<LoginScreen<B::Element>>,
+    ) -> Result<()> {
+        let username = credentials.username.as_str();
+        traced(info_span!("login", username), || {
+            self.check_abc_window()?;
+
+            let fields = &self.field_maps.login;
+            let login_screen = match login_screen {
+                Some(login_screen) => login_screen,
+                None => self.load_login_screen()?,
+            };
+            self.set_text_box_value(login_screen.element(), fields.index("username")?, username)?;
+            self.handle_login_popups(
+                "Encountered unexpected popup after entering username. Your username is probably \
+                 incorrect",
+            )?;
+            self.type_password(
+                login_screen.element(),
+                fields.index("password")?,
+                &credentials.password,
+            )?;
+            self.verify_login(&login_screen, username)?;
+            self.user = Some(username.to_string());
+            Ok(())
+        })
+    }
+
+    /// Type `password` into a text box like [`AbcSession::set_text_box_value`] does, wiping the
+    /// keys from memory afterwards. The password is traced as `***`, and errors from typing are
+    /// replaced so that the keys, and with them the password, cannot leak through the error
+    /// message
+    fn type_password(
+        &self,
+        screen: &B::Element,
+        box_index: usize,
+        password: &Password,
+    ) -> Result<()> {
+        let span = debug_span!(
+            "type_password",
+            screen = Empty,
+            field = Empty,
+            index = box_index
+        );
+        self.record_text_box(&span, screen, box_index);
+        traced(span, || {
+            let text_box = self.text_box_at(screen, box_index)?;
+            self.backend.click(&text_box)?;
+            // Reserve enough room up front so the password is never left behind by a reallocation,
+            // even if every character of it has to be escaped
+            let keys = Keys::with_capacity(password.expose().len() * 3 + 16)
+                .key(Key::Delete)
+                .text(password.expose())
+                .key(Key::Enter);
+            let traced_keys = Keys::new().key(Key::Delete).text(REDACTED).key(Key::Enter);
+            self.send_keys_as(&text_box, &keys, &traced_keys, self.timing.short())
+                .map_err(|e| match e {
+                    AbcError::Automation(e) => e.with_message("could not type the password").into(),
+                    e => e,
+                })
+        })
+    }
+
+    fn verify_login(&self, login_screen: &LoginScreen<B::Element>, username: &str) -> Result<()> {
+        self.check_abc_window()?;
+
+        let all_text_boxes = self.backend.text_boxes(login_screen.element())?;
+        let only_three_text_boxes = all_text_boxes.len() == 3;
+        if !only_three_text_boxes {
+            return Err(AbcError::WrongScreen {
+                expected: format!("{} (3 text boxes)", LoginScreen::<B::Element>::TITLE),
+                actual: format!(
+                    "{} ({} text boxes)",
+                    self.backend.name(login_screen.element())?,
+                    all_text_boxes.len()
+                ),
+            });
+        }
+        self.handle_login_popups(
+            "Encountered popup while logging into ABC. You are not logged in",
+        )?;
+        let username_index = self.field_maps.login.index("username")?;
+        let entered_username = self.read_text_box_value(login_screen.element(), username_index)?;
+        if entered_username.to_lowercase() != username.to_lowercase() {
+            return Err(AbcError::VerificationFailed {
+                field: "username".to_string(),
+                expected: username.to_string(),
+                actual: entered_username,
+            });
+        }
+        Ok(())
+    }
+
+    /// Give Client4 a moment to complain about the login, then handle any popups. A popup that
+    /// fails the login is reported with `text`
+    fn handle_login_popups(&self, text: &str) -> Result<()> {
+        if !self.wait_for_popup("error_popup", SECONDS, ERROR)? {
+            return Ok(());
+        }
+        match self.handle_popups() {
+            Err(AbcError::UnexpectedPopup { popup, .. }) => Err(AbcError::UnexpectedPopup {
+                popup,
+                text: text.to_string(),
+            }),
+            other => other.map(|_| ()),
+        }
+    }
+
+    /// Return `Err(AbcError::WrongScreen)` unless the session's window is the Client4 window
+    fn check_abc_window(&self) -> Result<()> {
+        let abc_window_name = self.backend.name(self.abc_window()?)?;
+        if !abc_window_name.contains("ABC Accounting Client") {
+            return Err(AbcError::WrongScreen {
+                expected: "ABC Accounting Client".to_string(),
+                actual: abc_window_name,
+            });
+        }
+        Ok(())
     }
-    set_text_box_value(&login_screen, 0, password)?;
-    verify_login(abc_window, &login_screen, username)?;
-    Ok(())
 }
@@ -0,0 +1,140 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bigdecimal::{BigDecimal, RoundingMode, Signed};
+
+use crate::AbcError;
+
+/// An amount of money, such as the list price or cost of an [`crate::inventory::Item`]. It is
+/// kept as an exact decimal, so prices like 11.24 survive parsing, arithmetic and being entered
+/// into Client4 unchanged
+///
+/// Amounts parse from the way Client4 and its reports print them:
+///
+/// ```
+/// use abc_uiautomation::Money;
+///
+/// let price: Money = ".20".parse().unwrap();
+/// assert_eq!(price.to_string(), "0.20");
+/// assert_eq!("1,234.5".parse::<Money>().unwrap().to_string(), "1234.50");
+/// assert_eq!("11.24-".parse::<Money>().unwrap().to_string(), "-11.24");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(BigDecimal);
+
+impl Money {
+    /// The exact amount
+    pub fn amount(&self) -> &BigDecimal {
+        &self.0
+    }
+
+    /// The amount rounded half up to whole cents, which is what Client4 keeps of it. Compare
+    /// these to tell whether entering an amount would change what Client4 holds
+    pub fn to_cents(&self) -> Money {
+        Money(self.0.with_scale_round(2, RoundingMode::HalfUp))
+    }
+
+    /// Whether the amount is below zero
+    pub fn is_negative(&self) -> bool {
+        self.0.is_negative()
+    }
+
+    /// The amount of one unit, when this is the total for `quantity` units, eg. a line of an
+    /// invoice. The result is exact up to rounding of repeating decimals, and a `quantity` of 0
+    /// leaves the amount unchanged
+    pub fn per_unit(&self, quantity: isize) -> Money {
+        match quantity {
+            0 => self.clone(),
+            quantity => Money(&self.0 / BigDecimal::from(quantity as i64)),
+        }
+    }
+}
+
+impl From<BigDecimal> for Money {
+    fn from(amount: BigDecimal) -> Self {
+        Money(amount)
+    }
+}
+
+impl From<Money> for BigDecimal {
+    fn from(money: Money) -> Self {
+        money.0
+    }
+}
+
+/// Two decimals, rounded half up, which is how Client4 expects amounts to be entered
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_cents().0)
+    }
+}
+
+impl FromStr for Money {
+    type Err = AbcError;
+
+    /// Parse an amount as Client4 prints it. A leading `$`, thousands separators, a missing
+    /// leading zero (`.20`), and a minus sign on either side of the number are accepted
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || AbcError::Parse {
+            field: "amount".to_string(),
+            value: s.to_string(),
+        };
+        let text = s.trim();
+        let (negative, text) = match (text.strip_prefix('-'), text.strip_suffix('-')) {
+            (Some(rest), None) | (None, Some(rest)) => (true, rest.trim()),
+            (None, None) => (false, text),
+            (Some(_), Some(_)) => return Err(error()),
+        };
+        let text = text.strip_prefix('$').unwrap_or(text);
+
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        if whole.contains(',') {
+            let mut groups = whole.split(',');
+            let first = groups.next().unwrap_or_default();
+            if first.is_empty() || first.len() > 3 || groups.any(|group| group.len() != 3) {
+                return Err(error());
+            }
+        }
+        let whole = whole.replace(',', "");
+        let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !is_digits(&whole) || !is_digits(fraction) {
+            return Err(error());
+        }
+
+        let sign = if negative { "-" } else { "" };
+        let whole = if whole.is_empty() { "0" } else { &whole };
+        let fraction = if fraction.is_empty() { "0" } else { fraction };
+        BigDecimal::from_str(&format!("{sign}{whole}.{fraction}"))
+            .map(Money)
+            .map_err(|_| error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Money;
+    use crate::AbcError;
+
+    #[test]
+    fn test_parse_money() {
+        let parse = |s: &str| s.parse::<Money>().map(|money| money.to_string());
+        assert_eq!(parse("11.24").unwrap(), "11.24");
+        assert_eq!(parse(".20").unwrap(), "0.20");
+        assert_eq!(parse("-.20").unwrap(), "-0.20");
+        assert_eq!(parse(" 1,234,567.891 ").unwrap(), "1234567.89");
+        assert_eq!(parse("$5").unwrap(), "5.00");
+        assert_eq!(parse("6.90-").unwrap(), "-6.90");
+        for bad in ["", ".", "-", "1,23.00", "12,3456", "4.99.1", "FREE", "-5-"] {
+            match bad.parse::<Money>() {
+                Err(AbcError::Parse { value, .. }) => assert_eq!(value, bad),
+                other => panic!("expected {bad:?} not to parse, got {other:?}"),
+            }
+        }
+
+        // Exact where f32 is not
+        let total: Money = "33.72".parse().unwrap();
+        assert_eq!(total.per_unit(3), "11.24".parse().unwrap());
+        assert!(total.per_unit(-3).is_negative());
+        assert_eq!(total.per_unit(7).to_cents(), "4.82".parse().unwrap());
+    }
+}
@@ -0,0 +1,341 @@
+use std::fmt;
+
+use tracing::info;
+
+use crate::keys::Keys;
+use crate::{AbcError, AbcSession, Backend, Result};
+
+/// Title of the dialog Client4 shows when Ctrl+N would throw away unsaved changes
+pub const SAVE_CHANGES: &str = "Save changes before proceeding?";
+/// Title of the dialog Client4 shows when a new UPC is entered on an item
+pub const ADD_TO_UPC: &str = "Add to UPC";
+/// Title of the dialog Client4 shows before deleting a record, such as a UPC
+pub const DELETE_RECORD: &str = "Delete Record";
+/// Title of the dialog Client4 shows when a record, such as an item, is not on file
+pub const NOT_FOUND: &str = "Not Found";
+/// Title of the dialog Client4 shows when an operation is not allowed, eg. when nobody is logged in
+pub const INFORMATION: &str = "Information";
+/// Title of the dialog Client4 shows when something went wrong
+pub const ERROR: &str = "Error";
+
+/// Class of the labels that hold the message of a Client4 dialog
+const LABEL_CLASS: &str = "ThunderRT6Label";
+/// Class of the buttons of a Client4 dialog
+const BUTTON_CLASS: &str = "ThunderRT6CommandButton";
+
+/// The text of a Client4 dialog, read from its title, labels, and buttons
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PopupInfo {
+    /// The full title of the dialog
+    pub title: String,
+    /// The text of every label on the dialog, one per line
+    pub message: String,
+    /// The captions of the buttons on the dialog, eg. `["&Yes", "&No"]`
+    pub buttons: Vec<String>,
+}
+
+impl fmt::Display for PopupInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}'", self.title)?;
+        if !self.message.is_empty() {
+            write!(f, " saying \"{}\"", self.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// What [`AbcSession::handle_popups`] does when it finds a popup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopupPolicy {
+    /// Answer the popup with its accept keys and carry on
+    Accept,
+    /// Answer the popup with its decline keys and carry on
+    Decline,
+    /// Dismiss the popup and fail with `AbcError::UnexpectedPopup`
+    Fail,
+    /// Dismiss the popup and carry on, only recording that it was seen
+    Collect,
+}
+
+/// How to recognize and answer one kind of Client4 popup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PopupRule {
+    /// Text contained in the title of the popup
+    pub title: String,
+    /// What to do when the popup is found
+    pub policy: PopupPolicy,
+    /// Keys that answer "yes" to the popup. Also used to dismiss it
+    pub accept_keys: String,
+    /// Keys that answer "no" to the popup
+    pub decline_keys: String,
+    /// Explanation used in the error when the policy is [`PopupPolicy::Fail`]
+    pub message: String,
+    /// Only look for the popup beneath the Client4 window instead of the entire desktop
+    pub within_abc_window: bool,
+}
+
+impl PopupRule {
+    /// A rule for popups whose title contains `title`. It is answered with `{enter}` to accept
+    /// and `{right}{enter}` to decline, which picks the second button
+    pub fn new(title: &str, policy: PopupPolicy) -> Self {
+        PopupRule {
+            title: title.to_string(),
+            policy,
+            accept_keys: "{enter}".to_string(),
+            decline_keys: "{right}{enter}".to_string(),
+            message: format!("encountered the '{title}' popup"),
+            within_abc_window: false,
+        }
+    }
+
+    /// Answer the popup with `accept` and `decline` instead of the defaults
+    pub fn keys(mut self, accept: &str, decline: &str) -> Self {
+        self.accept_keys = accept.to_string();
+        self.decline_keys = decline.to_string();
+        self
+    }
+
+    /// Explain what the popup means when it fails an operation
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = message.to_string();
+        self
+    }
+
+    /// Only look for the popup beneath the Client4 window
+    pub fn within_abc_window(mut self) -> Self {
+        self.within_abc_window = true;
+        self
+    }
+}
+
+/// Every popup the crate knows how to handle. Rules are checked in order, and the first one
+/// whose title matches a popup decides what happens to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PopupRegistry {
+    rules: Vec<PopupRule>,
+}
+
+impl Default for PopupRegistry {
+    fn default() -> Self {
+        PopupRegistry::new()
+            .with(PopupRule::new(SAVE_CHANGES, PopupPolicy::Accept))
+            .with(PopupRule::new(ADD_TO_UPC, PopupPolicy::Accept).keys("y", "n"))
+            .with(PopupRule::new(DELETE_RECORD, PopupPolicy::Accept).keys("y", "n"))
+            .with(PopupRule::new(NOT_FOUND, PopupPolicy::Collect))
+            .with(
+                PopupRule::new(INFORMATION, PopupPolicy::Fail)
+                    .message("Client4 refused the operation. The user is probably not logged in"),
+            )
+            .with(
+                PopupRule::new(ERROR, PopupPolicy::Fail)
+                    .message("Client4 reported an error")
+                    .within_abc_window(),
+            )
+    }
+}
+
+impl PopupRegistry {
+    /// Create a registry that handles no popups at all
+    pub fn new() -> Self {
+        PopupRegistry { rules: Vec::new() }
+    }
+
+    /// Add `rule`, replacing any rule with the same title, and return the updated registry
+    pub fn with(mut self, rule: PopupRule) -> Self {
+        match self.rules.iter_mut().find(|r| r.title == rule.title) {
+            Some(existing) => *existing = rule,
+            None => self.rules.push(rule),
+        }
+        self
+    }
+
+    /// Change the policy of the rule for `title`, returning the updated registry. Does nothing if
+    /// there is no such rule
+    pub fn with_policy(mut self, title: &str, policy: PopupPolicy) -> Self {
+        if let Some(rule) = self.rules.iter_mut().find(|r| r.title == title) {
+            rule.policy = policy;
+        }
+        self
+    }
+
+    /// The rule for popups titled `title`, if there is one
+    pub fn get(&self, title: &str) -> Option<&PopupRule> {
+        self.rules.iter().find(|r| r.title == title)
+    }
+
+    /// Every rule in the order they are checked
+    pub fn rules(&self) -> &[PopupRule] {
+        &self.rules
+    }
+}
+
+/// A popup that [`AbcSession::handle_popups`] found and dealt with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeenPopup {
+    /// The text of the popup
+    pub info: PopupInfo,
+    /// What was done with it
+    pub policy: PopupPolicy,
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Deal with every popup in the session's [`PopupRegistry`] that is currently open, according
+    /// to its policy, until none are left
+    ///
+    /// # Returns
+    ///
+    /// The popups that were found, in the order they were handled
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::UnexpectedPopup)` as soon as a popup with the
+    /// [`PopupPolicy::Fail`] policy is found, or `Err(AbcError::Timeout)` if a popup does not
+    /// close after it was answered
+    pub fn handle_popups(&self) -> Result<Vec<SeenPopup>> {
+        self.handle_popups_with(&self.popups)
+    }
+
+    /// Same as [`AbcSession::handle_popups`], but uses `registry` instead of the session's
+    pub fn handle_popups_with(&self, registry: &PopupRegistry) -> Result<Vec<SeenPopup>> {
+        let mut seen = Vec::new();
+        'search: loop {
+            for rule in registry.rules() {
+                let Some(popup) = self.find_popup_matching(rule)? else {
+                    continue;
+                };
+                let info = self.popup_info(&popup)?;
+                info!(
+                    title = %info.title,
+                    message = %info.message,
+                    policy = ?rule.policy,
+                    "popup"
+                );
+                let keys = match rule.policy {
+                    PopupPolicy::Decline => &rule.decline_keys,
+                    _ => &rule.accept_keys,
+                };
+                self.send_keys(&popup, &Keys::raw(keys), self.timing.short())?;
+                if rule.policy == PopupPolicy::Fail {
+                    return Err(AbcError::UnexpectedPopup {
+                        popup: info,
+                        text: rule.message.clone(),
+                    });
+                }
+                self.wait_for(
+                    "popup_close",
+                    self.timing.short_wait_ms * 30,
+                    &format!("the {info} popup to close"),
+                    || Ok(self.find_popup_matching(rule)?.is_none()),
+                )?;
+                seen.push(SeenPopup {
+                    info,
+                    policy: rule.policy,
+                });
+                continue 'search;
+            }
+            return Ok(seen);
+        }
+    }
+
+    /// Read the title, message, and button captions of `popup`. The message is the text of every
+    /// label on the dialog, in the order Client4 exposes them, joined by newlines
+    pub fn popup_info(&self, popup: &B::Element) -> Result<PopupInfo> {
+        let names = |classname| -> Result<Vec<String>> {
+            let mut names = Vec::new();
+            for element in self.backend.descendants(popup, classname)? {
+                let name = self.backend.name(&element)?;
+                if !name.trim().is_empty() {
+                    names.push(name.trim().to_string());
+                }
+            }
+            Ok(names)
+        };
+        Ok(PopupInfo {
+            title: self.backend.name(popup)?,
+            message: names(LABEL_CLASS)?.join("\n"),
+            buttons: names(BUTTON_CLASS)?,
+        })
+    }
+
+    /// Wait for the popup titled `title` to appear, giving up after the delay of `operation`
+    ///
+    /// # Returns
+    ///
+    /// `Ok(true)` if the popup appeared, `Ok(false)` if it did not
+    pub fn wait_for_popup(
+        &self,
+        operation: &str,
+        default_timeout_ms: u64,
+        title: &str,
+    ) -> Result<bool> {
+        let rule = match self.popups.get(title) {
+            Some(rule) => rule.clone(),
+            None => PopupRule::new(title, PopupPolicy::Fail),
+        };
+        match self.wait_for(operation, default_timeout_ms, title, || {
+            Ok(self.find_popup_matching(&rule)?.is_some())
+        }) {
+            Ok(()) => Ok(true),
+            Err(AbcError::Timeout { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn find_popup_matching(&self, rule: &PopupRule) -> Result<Option<B::Element>> {
+        let from = match rule.within_abc_window {
+            true => Some(self.abc_window()?),
+            false => None,
+        };
+        Ok(self.backend.find_popup(from, &rule.title, 0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PopupPolicy, PopupRegistry, PopupRule, INFORMATION, SAVE_CHANGES};
+    use crate::backend::FakeBackend;
+    use crate::{AbcError, AbcSession, Timing};
+
+    #[test]
+    fn test_handle_popups() {
+        let fake = FakeBackend::new();
+        let abc_window = fake.add_screen("ABC Accounting Client");
+        fake.add_popup("Item Notes");
+        fake.add_popup(SAVE_CHANGES);
+        let popups = PopupRegistry::default()
+            .with(PopupRule::new("Item Notes", PopupPolicy::Collect))
+            .with_policy(SAVE_CHANGES, PopupPolicy::Decline);
+
+        let session = AbcSession::with_window(fake, abc_window)
+            .with_timing(Timing::default().with_scale(0.1))
+            .with_popups(popups);
+        let seen = session.handle_popups().unwrap();
+        let seen: Vec<_> = seen
+            .iter()
+            .map(|p| (p.info.title.as_str(), p.policy))
+            .collect();
+        assert_eq!(
+            seen,
+            [
+                (SAVE_CHANGES, PopupPolicy::Decline),
+                ("Item Notes", PopupPolicy::Collect)
+            ]
+        );
+        assert!(session.handle_popups().unwrap().is_empty());
+
+        session.backend().add_popup_with(
+            INFORMATION,
+            "You do not have access to\nthis function",
+            &["OK"],
+        );
+        match session.handle_popups() {
+            Err(AbcError::UnexpectedPopup { popup, .. }) => {
+                assert_eq!(popup.title, INFORMATION);
+                assert_eq!(popup.message, "You do not have access to\nthis function");
+                assert_eq!(popup.buttons, ["OK"]);
+            }
+            other => panic!("expected UnexpectedPopup, got {other:?}"),
+        }
+        assert!(!session.popup_is_open(INFORMATION).unwrap());
+    }
+}
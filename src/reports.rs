@@ -1,9 +1,13 @@
-use uiautomation::UIAutomation;
+use tracing::{debug_span, info_span};
 
+use crate::keys::{Key, Keys};
+use crate::popups::{ERROR, INFORMATION};
+use crate::trace::traced;
 use crate::{
-    create_matcher_wrapper, set_text_box_value, set_text_box_value_no_enter, wait, UIElement,
-    SHORT_WAIT_MS,
+    AbcSession, Backend, ReportMenuScreen, ReportOptionsScreen, Result, SpecialReportsScreen,
 };
+#[cfg(windows)]
+use crate::{UIElement, UiaBackend};
 
 /// Control ABC Client4 to generate any simple report that follows the pattern:
 /// * Open F10
@@ -24,19 +28,20 @@ use crate::{
 ///
 /// # Returns
 ///
-/// Will return unit type if successful. Return `uiautomation::Error` if UI manipulation fails at
+/// Will return unit type if successful. Return `AbcError` if UI manipulation fails at
 /// any point
 ///
 /// # Errors
 ///
-/// Will return `Err(uiautomation::Error)` if UI manipulation fails at any point
+/// Will return `Err(AbcError)` if UI manipulation fails at any point
+#[cfg(windows)]
 pub fn generate_simple_report(
     abc_window: &UIElement,
     menu: &str,
     report: &str,
     starting_at: &str,
     ending_with: &str,
-) -> uiautomation::Result<()> {
+) -> Result<()> {
     generate_simple_report_with_skips(abc_window, menu, report, 0, starting_at, ending_with)
 }
 
@@ -62,12 +67,13 @@ pub fn generate_simple_report(
 ///
 /// # Returns
 ///
-/// Will return unit type if successful. Return `uiautomation::Error` if UI manipulation fails at
+/// Will return unit type if successful. Return `AbcError` if UI manipulation fails at
 /// any point
 ///
 /// # Errors
 ///
-/// Will return `Err(uiautomation::Error)` if UI manipulation fails at any point
+/// Will return `Err(AbcError)` if UI manipulation fails at any point
+#[cfg(windows)]
 pub fn generate_simple_report_with_skips(
     abc_window: &UIElement,
     menu: &str,
@@ -75,19 +81,9 @@ pub fn generate_simple_report_with_skips(
     nskips: u8,
     starting_at: &str,
     ending_with: &str,
-) -> uiautomation::Result<()> {
-    abc_window.send_keys(&format!("{{F10}}{}", menu), SHORT_WAIT_MS * 3)?;
-    wait(SHORT_WAIT_MS * 5);
-    abc_window.send_keys(&format!("{}{{enter}}", report), SHORT_WAIT_MS / 2)?;
-    for _ in 0..nskips {
-        abc_window.send_keys("{enter}", SHORT_WAIT_MS / 2)?;
-    }
-    wait(SHORT_WAIT_MS * 5);
-    abc_window.send_keys(
-        &format!("{{enter}}{}{{enter}}{}{{enter}}t", starting_at, ending_with),
-        SHORT_WAIT_MS / 2,
-    )?;
-    Ok(())
+) -> Result<()> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone())
+        .generate_simple_report_with_skips(menu, report, nskips, starting_at, ending_with)
 }
 
 /// Control ABC Client4 to generate a 1-1 report (INVENTORY LISTING)
@@ -100,28 +96,40 @@ pub fn generate_simple_report_with_skips(
 ///
 /// # Returns
 ///
-/// Will return unit type if successful. Return `uiautomation::Error` if UI manipulation fails at
+/// Will return unit type if successful. Return `AbcError` if UI manipulation fails at
 /// any point
 ///
 /// # Errors
 ///
-/// Will return `Err(uiautomation::Error)` if UI manipulation fails at any point
+/// Will return `Err(AbcError)` if UI manipulation fails at any point
+#[cfg(windows)]
 pub fn generate_report_11(
     abc_window: &UIElement,
     starting_sku: &str,
     ending_sku: &str,
-) -> uiautomation::Result<()> {
-    abc_window.send_keys("{F10}1", SHORT_WAIT_MS * 3)?;
-    wait(SHORT_WAIT_MS * 5);
-    abc_window.send_keys("1{enter}", SHORT_WAIT_MS / 2)?;
-    wait(SHORT_WAIT_MS * 5);
-    abc_window.send_keys("I", SHORT_WAIT_MS / 2)?;
-    wait(SHORT_WAIT_MS * 5);
-    abc_window.send_keys(
-        &format!("{{enter}}{}{{enter}}{}{{enter}}t", starting_sku, ending_sku),
-        SHORT_WAIT_MS / 2,
-    )?;
-    Ok(())
+) -> Result<()> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone())
+        .generate_report_11(starting_sku, ending_sku)
+}
+
+/// Control ABC Client4 to open the F10-7 "Utilities - Special User Reports" screen
+///
+/// # Arguments
+///
+/// * `abc_window` - The `UIElement` representing the Client4 window
+///
+/// # Returns
+///
+/// The [`SpecialReportsScreen`] if successful
+///
+/// # Errors
+///
+/// Will return `Err(AbcError::UnexpectedPopup)` if ABC answers with an "Information" popup, which
+/// usually means the user is not logged in. Return `Err(AbcError::Automation)` if UI
+/// manipulation fails at any point
+#[cfg(windows)]
+pub fn load_special_reports_screen(abc_window: &UIElement) -> Result<SpecialReportsScreen> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).load_special_reports_screen()
 }
 
 /// Control ABC Client4 to generate a 7-10 report (Export Data to SQL)
@@ -137,26 +145,302 @@ pub fn generate_report_11(
 /// the ABC provided default is used
 /// * `ending_with` - The value to end the exported report at. If a null string is passed, then the
 /// ABC provided default is used
-pub fn generate_report_710(abc_window: &UIElement, file: &str) -> uiautomation::Result<()> {
-    let automation = UIAutomation::new()?;
-    abc_window.send_keys("{F10}7", SHORT_WAIT_MS * 3)?;
-    wait(SHORT_WAIT_MS);
-    if let Ok(_) = crate::create_matcher_wrapper(&automation)?
-        .classname("ThunderRT6FormDC")
-        .name("Information")
-        .find_first()
-    {
-        return Err(uiautomation::Error::new(2, &format!("ABC threw an 'Information' popup when loading the 7 report file. This probably because the user is not logged in.")));
+///
+/// # Errors
+///
+/// Return `Err(AbcError::UnexpectedPopup)` with the text of the popup if ABC refuses the report,
+/// eg. because the user is not logged in or `file` is not a data file
+#[cfg(windows)]
+pub fn generate_report_710(abc_window: &UIElement, file: &str) -> Result<()> {
+    AbcSession::with_window(UiaBackend::new()?, abc_window.clone()).generate_report_710(file)
+}
+
+/// Keys that fill in the "Starting at" and "Ending with" range of a report and pick text output
+fn range_keys(starting_at: &str, ending_with: &str) -> Keys {
+    Keys::new()
+        .key(Key::Enter)
+        .text(starting_at)
+        .key(Key::Enter)
+        .text(ending_with)
+        .key(Key::Enter)
+        .text("t")
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Same as [`generate_simple_report`]
+    pub fn generate_simple_report(
+        &self,
+        menu: &str,
+        report: &str,
+        starting_at: &str,
+        ending_with: &str,
+    ) -> Result<()> {
+        self.generate_simple_report_with_skips(menu, report, 0, starting_at, ending_with)
+    }
+
+    /// Same as [`generate_simple_report_with_skips`]
+    pub fn generate_simple_report_with_skips(
+        &self,
+        menu: &str,
+        report: &str,
+        nskips: u8,
+        starting_at: &str,
+        ending_with: &str,
+    ) -> Result<()> {
+        traced(info_span!("generate_report", menu, report), || {
+            let abc_window = self.abc_window()?;
+            self.open_report_menu(menu)?;
+            self.send_keys(
+                abc_window,
+                &Keys::new().text(report).key(Key::Enter),
+                self.timing.short() / 2,
+            )?;
+            for _ in 0..nskips {
+                self.send_keys(
+                    abc_window,
+                    &Keys::new().key(Key::Enter),
+                    self.timing.short() / 2,
+                )?;
+            }
+            self.wait_for_report_options()?;
+            self.send_keys(
+                abc_window,
+                &range_keys(starting_at, ending_with),
+                self.timing.short() / 2,
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Same as [`generate_report_11`]
+    pub fn generate_report_11(&self, starting_sku: &str, ending_sku: &str) -> Result<()> {
+        traced(
+            info_span!("generate_report", menu = "1", report = "1"),
+            || {
+                let abc_window = self.abc_window()?;
+                self.open_report_menu("1")?;
+                self.send_keys(
+                    abc_window,
+                    &Keys::new().text("1").key(Key::Enter),
+                    self.timing.short() / 2,
+                )?;
+                self.wait_for_report_selected()?;
+                self.send_keys(abc_window, &Keys::new().text("I"), self.timing.short() / 2)?;
+                self.wait_for_report_options()?;
+                self.send_keys(
+                    abc_window,
+                    &range_keys(starting_sku, ending_sku),
+                    self.timing.short() / 2,
+                )?;
+                Ok(())
+            },
+        )
+    }
+
+    /// Same as [`load_special_reports_screen`]
+    pub fn load_special_reports_screen(&self) -> Result<SpecialReportsScreen<B::Element>> {
+        traced(
+            debug_span!(
+                "load_screen",
+                screen = SpecialReportsScreen::<B::Element>::TITLE
+            ),
+            || {
+                let abc_window = self.abc_window()?;
+                self.send_keys(
+                    abc_window,
+                    &Keys::new().key(Key::F(10)).text("7"),
+                    self.timing.short() * 3,
+                )?;
+                let title = SpecialReportsScreen::<B::Element>::TITLE;
+                self.wait_for(
+                    "load_special_reports_screen",
+                    self.timing.short_wait_ms * 30,
+                    title,
+                    || Ok(self.screen_is_open(title)? || self.popup_is_open(INFORMATION)?),
+                )?;
+                self.handle_popups()?;
+                SpecialReportsScreen::find(&self.backend)
+            },
+        )
+    }
+
+    /// Same as [`generate_report_710`]
+    pub fn generate_report_710(&self, file: &str) -> Result<()> {
+        traced(
+            info_span!("generate_report", menu = "7", report = "10"),
+            || {
+                let fields = &self.field_maps;
+                let special_reports_screen = self.load_special_reports_screen()?;
+                let special_reports_screen = special_reports_screen.element();
+                let report_index = fields.special_reports.index("report")?;
+                let file_index = fields.special_reports.index("file")?;
+                self.set_text_box_value(special_reports_screen, report_index, "10")?;
+                self.set_text_box_value(special_reports_screen, file_index, file)?;
+                self.set_text_box_value_no_enter(special_reports_screen, file_index, "N")?;
+                let report_options_screen = self.wait_for_report_options()?;
+                self.set_text_box_value_no_enter(
+                    report_options_screen.element(),
+                    fields.report_options.index("output")?,
+                    "S",
+                )?;
+                Ok(())
+            },
+        )
+    }
+
+    /// Open the report menu `menu` with F10 and wait for Client4 to show it. If Client4 refuses
+    /// with a popup instead, eg. because nobody is logged in, it is handled like any other popup
+    fn open_report_menu(&self, menu: &str) -> Result<()> {
+        traced(
+            debug_span!(
+                "load_screen",
+                screen = ReportMenuScreen::<B::Element>::TITLE,
+                menu
+            ),
+            || {
+                self.send_keys(
+                    self.abc_window()?,
+                    &Keys::new().key(Key::F(10)).text(menu),
+                    self.timing.short() * 3,
+                )?;
+                let title = ReportMenuScreen::<B::Element>::TITLE;
+                self.wait_for("report_menu", self.timing.short_wait_ms * 30, title, || {
+                    Ok(self.screen_is_open(title)? || self.popup_is_open(INFORMATION)?)
+                })?;
+                self.handle_popups()?;
+                Ok(())
+            },
+        )
+    }
+
+    /// Wait for Client4 to close the [`ReportMenuScreen`] once a report has been picked from it,
+    /// which is when it takes the report's own options. A popup opened instead is handled like
+    /// any other popup
+    fn wait_for_report_selected(&self) -> Result<()> {
+        let title = ReportMenuScreen::<B::Element>::TITLE;
+        self.wait_for(
+            "report_selected",
+            self.timing.short_wait_ms * 30,
+            "the report menu to close",
+            || {
+                Ok(!self.screen_is_open(title)?
+                    || self.popup_is_open(ERROR)?
+                    || self.popup_is_open(INFORMATION)?)
+            },
+        )?;
+        self.handle_popups()?;
+        Ok(())
+    }
+
+    /// Wait for Client4 to open the [`ReportOptionsScreen`] after a report has been picked. If
+    /// Client4 refuses the report with a popup instead, it is handled like any other popup
+    fn wait_for_report_options(&self) -> Result<ReportOptionsScreen<B::Element>> {
+        traced(
+            debug_span!(
+                "load_screen",
+                screen = ReportOptionsScreen::<B::Element>::TITLE
+            ),
+            || {
+                let title = ReportOptionsScreen::<B::Element>::TITLE;
+                self.wait_for(
+                    "report_options_screen",
+                    self.timing.short_wait_ms * 30,
+                    title,
+                    || {
+                        Ok(self.screen_is_open(title)?
+                            || self.popup_is_open(ERROR)?
+                            || self.popup_is_open(INFORMATION)?)
+                    },
+                )?;
+                self.handle_popups()?;
+                ReportOptionsScreen::find(&self.backend)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{FakeBackend, FakeElement};
+    use crate::{AbcSession, Backend, ReportMenuScreen, ReportOptionsScreen, Timing};
+
+    /// A Client4 whose report `menu` opens "Inventory - Reports", and where picking report `pick`
+    /// from it closes the menu. The report options screen opens once `then` is typed
+    fn client4(pick: &'static str, then: &'static str) -> AbcSession<FakeBackend> {
+        let fake = FakeBackend::new();
+        let abc_window = fake.add_screen("ABC Accounting Client");
+        fake.on_keys("{F10}", |fake, _, _| {
+            fake.add_screen("Inventory - Reports");
+        });
+        fake.on_keys(pick, move |fake, _, keys| {
+            if keys == pick {
+                fake.close(fake.find_screen("Inventory - Reports").unwrap());
+            }
+        });
+        fake.on_keys(then, move |fake, _, keys| {
+            if keys == then {
+                let screen = fake.add_screen("r screen");
+                fake.add_text_boxes(screen, &["", "", "", ""]);
+            }
+        });
+        AbcSession::with_window(fake, abc_window).with_timing(Timing::default().with_scale(0.1))
+    }
+
+    #[test]
+    fn test_report_flows_reach_report_options() {
+        let session = client4("3{Enter}", "{Enter}");
+        session
+            .generate_simple_report_with_skips("1", "3", 1, "A", "Z")
+            .unwrap();
+        let keys: Vec<String> = session
+            .backend
+            .sent_keys()
+            .into_iter()
+            .map(|(_, k)| k)
+            .collect();
+        assert_eq!(
+            keys,
+            ["{F10}1", "3{Enter}", "{Enter}", "{Enter}A{Enter}Z{Enter}t"]
+        );
+
+        let session = client4("1{Enter}", "I");
+        session.generate_report_11("A", "Z").unwrap();
+        let keys: Vec<String> = session
+            .backend
+            .sent_keys()
+            .into_iter()
+            .map(|(_, k)| k)
+            .collect();
+        assert_eq!(
+            keys,
+            ["{F10}1", "1{Enter}", "I", "{Enter}A{Enter}Z{Enter}t"]
+        );
+
+        // Without a report menu, the report is not typed into whatever screen is open
+        let session = client4("1{Enter}", "I");
+        session.backend.on_keys("{F10}", |fake, _, _| {
+            fake.close(fake.find_screen("Inventory - Reports").unwrap());
+        });
+        assert!(session.generate_report_11("A", "Z").is_err());
+        assert_eq!(session.backend.sent_keys().len(), 1);
+    }
+
+    #[test]
+    fn test_report_screens_are_not_mistaken_for_others() {
+        let session = client4("1{Enter}", "I");
+        session.backend.on_keys("{F10}", |fake, _, _| {
+            fake.close(fake.find_screen("Inventory - Reports").unwrap());
+        });
+        session
+            .backend
+            .add_screen("Utilities - Special User Reports");
+        session.backend.add_screen("Vendor screen");
+        assert!(!session.screen_is_open(<ReportMenuScreen>::TITLE).unwrap());
+        assert!(!session
+            .screen_is_open(<ReportOptionsScreen>::TITLE)
+            .unwrap());
+        assert!(ReportOptionsScreen::<FakeElement>::find(&session.backend).is_err());
+        assert!(session.generate_report_11("A", "Z").is_err());
+        assert_eq!(session.backend.sent_keys().len(), 1);
     }
-    let special_reports_screen = create_matcher_wrapper(&automation)?
-        .contains_name("Utilities - Special User Reports")
-        .find_first()?;
-    set_text_box_value(&special_reports_screen, 1, "10")?;
-    set_text_box_value(&special_reports_screen, 0, file)?;
-    set_text_box_value_no_enter(&special_reports_screen, 0, "N")?;
-    let report_screen = create_matcher_wrapper(&automation)?
-        .name("r screen")
-        .find_first()?;
-    set_text_box_value_no_enter(&report_screen, 10, "S")?;
-    Ok(())
 }
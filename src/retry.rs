@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use tracing::{debug, debug_span};
+
+use crate::trace::traced;
+use crate::{wait, AbcError, AbcSession, Backend, Money, Result};
+
+/// How a value read back from a text box is compared with the value that was entered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    /// The text box must hold exactly what was entered
+    Exact,
+    /// Leading and trailing whitespace is ignored, since Client4 pads some fields with spaces
+    Trimmed,
+    /// Whitespace and case are ignored, for fields Client4 converts to upper case
+    IgnoreCase,
+    /// Both are read as a [`Money`] amount and compared by value, since Client4 shows prices
+    /// the way it formats them, eg. `.20` or `1,299.00`. Text that is not an amount never matches
+    Amount,
+}
+
+impl Comparison {
+    /// Whether `actual` counts as `expected` under this comparison
+    pub fn matches(&self, expected: &str, actual: &str) -> bool {
+        match self {
+            Comparison::Exact => actual == expected,
+            Comparison::Trimmed => actual.trim() == expected.trim(),
+            Comparison::IgnoreCase => actual.trim().eq_ignore_ascii_case(expected.trim()),
+            Comparison::Amount => match (expected.parse::<Money>(), actual.parse::<Money>()) {
+                (Ok(expected), Ok(actual)) => expected == actual,
+                _ => false,
+            },
+        }
+    }
+}
+
+/// How often a setter enters a value before giving up on Client4 taking it, and how it checks
+/// that it did. Every setter, such as [`crate::inventory::set_desc`], writes the value, reads it
+/// back, and tries again until the value matches or `attempts` run out
+///
+/// ```toml
+/// attempts = 3
+/// backoff_ms = 200
+/// comparison = "ignore_case"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// How many times to enter the value, including the first time
+    pub attempts: u32,
+    /// How long to wait before the second attempt. The wait doubles with every attempt after that
+    pub backoff_ms: u64,
+    /// How to compare the value read back with the one entered. `None` uses the comparison that
+    /// suits each field, eg. [`Comparison::Amount`] for prices and [`Comparison::Trimmed`] for
+    /// descriptions
+    pub comparison: Option<Comparison>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 2,
+            backoff_ms: 0,
+            comparison: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Enter values up to `attempts` times, returning the updated policy
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts;
+        self
+    }
+
+    /// Wait `backoff_ms` before the second attempt, returning the updated policy
+    pub fn with_backoff(mut self, backoff_ms: u64) -> Self {
+        self.backoff_ms = backoff_ms;
+        self
+    }
+
+    /// Compare every field with `comparison`, returning the updated policy
+    pub fn with_comparison(mut self, comparison: Comparison) -> Self {
+        self.comparison = Some(comparison);
+        self
+    }
+
+    /// How long to wait before attempt number `attempt`, counting from 0
+    fn backoff(&self, attempt: u32) -> u64 {
+        match attempt {
+            0 => 0,
+            n => self.backoff_ms.saturating_mul(1 << (n - 1).min(16)),
+        }
+    }
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Enter `value` into the text box at `box_index` on `screen` and read it back, retrying
+    /// according to the session's [`RetryPolicy`]. `comparison` is the comparison that suits the
+    /// field, which the policy may override
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::VerificationFailed)` naming `field` if the text box does not hold
+    /// `value` after the last attempt. Forwards errors entering or reading the value
+    pub fn write_verified(
+        &self,
+        screen: &B::Element,
+        box_index: usize,
+        field: &str,
+        value: &str,
+        comparison: Comparison,
+    ) -> Result<()> {
+        traced(debug_span!("write_verified", field, value), || {
+            let comparison = self.retry.comparison.unwrap_or(comparison);
+            let mut actual = String::new();
+            for attempt in 0..self.retry.attempts.max(1) {
+                wait(self.retry.backoff(attempt));
+                self.set_text_box_value(screen, box_index, value)?;
+                actual = self.read_text_box_value(screen, box_index)?;
+                if comparison.matches(value, &actual) {
+                    return Ok(());
+                }
+                debug!(attempt, expected = value, actual, "verification failed");
+            }
+            Err(AbcError::VerificationFailed {
+                field: field.to_string(),
+                expected: value.to_string(),
+                actual,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Comparison, RetryPolicy};
+    use crate::backend::FakeBackend;
+    use crate::{AbcError, AbcSession};
+
+    #[test]
+    fn test_write_verified() {
+        let fake = FakeBackend::new();
+        let screen = fake.add_screen("Inventory - Items (I)");
+        fake.add_text_boxes(screen, &[""]);
+        // Client4 drops the first keystrokes while it is busy, then catches up
+        fake.on_keys("{Delete}", |fake, text_box, keys| {
+            let typed = keys
+                .trim_start_matches("{Delete}")
+                .trim_end_matches("{Enter}");
+            let dropped = fake.sent_keys().len() < 3;
+            fake.set_value(text_box, if dropped { &typed[1..] } else { typed });
+        });
+
+        let session = AbcSession::new(fake).with_retry(RetryPolicy::default().with_attempts(1));
+        match session.write_verified(&screen, 0, "desc", "WIDGET", Comparison::Trimmed) {
+            Err(AbcError::VerificationFailed { actual, .. }) => assert_eq!(actual, "IDGET"),
+            other => panic!("expected VerificationFailed, got {other:?}"),
+        }
+
+        let session = session.with_retry(RetryPolicy::default().with_attempts(3).with_backoff(1));
+        session
+            .write_verified(&screen, 0, "desc", "WIDGET", Comparison::Trimmed)
+            .unwrap();
+        assert_eq!(session.read_text_box_value(&screen, 0).unwrap(), "WIDGET");
+
+        assert!(Comparison::IgnoreCase.matches("WIDGET", "widget "));
+        assert!(!Comparison::Exact.matches("WIDGET", "WIDGET "));
+        assert!(Comparison::Amount.matches("1299.00", "1,299.00"));
+        assert!(Comparison::Amount.matches("0.20", ".20"));
+        assert!(!Comparison::Amount.matches("0.20", "2.00"));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::backend::DefaultBackend;
+use crate::{AbcError, Backend, Result};
+
+/// Return `Err(AbcError::WrongScreen)` unless the name of `element` contains `title`, or is
+/// `title` for the screens in [`EXACT_TITLES`]
+fn check_title<B: Backend>(backend: &B, element: &B::Element, title: &str) -> Result<()> {
+    let name = backend.name(element)?;
+    let matches = if EXACT_TITLES.contains(&title) {
+        name == title
+    } else {
+        name.contains(title)
+    };
+    if !matches {
+        return Err(AbcError::WrongScreen {
+            expected: title.to_string(),
+            actual: name,
+        });
+    }
+    Ok(())
+}
+
+/// Define a newtype around a screen element whose title is checked once, when it is created
+macro_rules! screen {
+    ($(#[$doc:meta])* $name:ident, $title:expr) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug)]
+        pub struct $name<E = <DefaultBackend as Backend>::Element>(E);
+
+        impl<E> $name<E> {
+            /// Text contained in the title of this screen, or the whole title if it is one of
+            /// [`EXACT_TITLES`]
+            pub const TITLE: &'static str = $title;
+
+            /// Wrap `element` after checking that its title matches [`Self::TITLE`]
+            ///
+            /// # Errors
+            ///
+            /// Return `Err(AbcError::WrongScreen)` if `element` is some other screen
+            pub fn new<B: Backend<Element = E>>(backend: &B, element: E) -> Result<Self> {
+                check_title(backend, &element, Self::TITLE)?;
+                Ok($name(element))
+            }
+
+            /// Find this screen if it is already open in Client4
+            ///
+            /// # Errors
+            ///
+            /// Return `Err(AbcError::Automation)` if the screen cannot be found
+            pub fn find<B: Backend<Element = E>>(backend: &B) -> Result<Self> {
+                if EXACT_TITLES.contains(&Self::TITLE) {
+                    return Ok($name(backend.find_screen_named(Self::TITLE)?));
+                }
+                Ok($name(backend.find_screen(Self::TITLE)?))
+            }
+
+            /// The underlying element of the screen
+            pub fn element(&self) -> &E {
+                &self.0
+            }
+
+            /// Unwrap the underlying element of the screen
+            pub fn into_element(self) -> E {
+                self.0
+            }
+        }
+    };
+}
+
+/// Title of every screen the crate drives, which are the screens that can be left open on top of
+/// the Client4 main menu
+pub const SCREEN_TITLES: &[&str] = &[
+    <InventoryScreen>::TITLE,
+    <CustomerScreen>::TITLE,
+    <InvoicesScreen>::TITLE,
+    <LoginScreen>::TITLE,
+    <ReportOptionsScreen>::TITLE,
+    <ReportMenuScreen>::TITLE,
+    <SpecialReportsScreen>::TITLE,
+];
+
+/// Titles that are only matched in full, because they are short enough to be part of the title
+/// of some unrelated window
+pub const EXACT_TITLES: &[&str] = &[<ReportOptionsScreen>::TITLE];
+
+/// Title of every screen that edits records, and can therefore hold unsaved changes
+pub(crate) const RECORD_SCREEN_TITLES: &[&str] = &[
+    <InventoryScreen>::TITLE,
+    <CustomerScreen>::TITLE,
+    <InvoicesScreen>::TITLE,
+];
+
+screen!(
+    /// The F10-I "Inventory - Items (I)" screen. See [`crate::inventory::load_inventory_screen`]
+    InventoryScreen,
+    "Inventory - Items (I)"
+);
+
+screen!(
+    /// The F10-C "Sales - Customers (C)" screen. See
+    /// [`crate::customer_file::load_customer_screen`]
+    CustomerScreen,
+    "Sales - Customers (C)"
+);
+
+screen!(
+    /// The F10-R "Sales - Invoices (R)" screen. See
+    /// [`crate::accounts_receivable::load_invoices_screen`]
+    InvoicesScreen,
+    "Sales - Invoices (R)"
+);
+
+screen!(
+    /// The F10-* "Utilities - System Date and Time" screen, which is where users log in. See
+    /// [`crate::load_login_screen`]
+    LoginScreen,
+    "Utilities - System Date and Time"
+);
+
+screen!(
+    /// The menu Client4 opens for F10 followed by the number of a report menu, eg. F10-1 for
+    /// "Inventory - Reports". Every report menu is titled after its module, followed by
+    /// " - Reports", which [`SpecialReportsScreen`] is not
+    ReportMenuScreen,
+    " - Reports"
+);
+
+screen!(
+    /// The screen Client4 opens once a report has been picked, where the range and output of the
+    /// report are entered. "r screen" is its whole title
+    ReportOptionsScreen,
+    "r screen"
+);
+
+screen!(
+    /// The F10-7 "Utilities - Special User Reports" screen. See
+    /// [`crate::reports::load_special_reports_screen`]
+    SpecialReportsScreen,
+    "Utilities - Special User Reports"
+);
@@ -0,0 +1,284 @@
+use std::cell::OnceCell;
+
+use crate::abc_window::ABC_WINDOW_TITLE;
+use crate::backend::DefaultBackend;
+use crate::screens::EXACT_TITLES;
+
+#[cfg(windows)]
+use crate::UiaBackend;
+use crate::{
+    field_maps, timing, wait_until, Backend, FieldMaps, PopupRegistry, Result, RetryPolicy, Timing,
+};
+
+/// A connection to a running Client4 instance. The session holds on to everything that would
+/// otherwise be set up again for every call: the [`Backend`] (and with it the `UIAutomation`
+/// instance), the Client4 window, the field maps, timing, popup rules and retry policy to use,
+/// and the user that logged in.
+///
+/// Every operation of the crate is available as a method on the session. The free functions are
+/// thin wrappers that start a new session for a single call, so prefer a session when driving
+/// Client4 through many operations in a row, eg. when updating thousands of items
+pub struct AbcSession<B: Backend = DefaultBackend> {
+    pub(crate) backend: B,
+    pub(crate) abc_window: OnceCell<B::Element>,
+    pub(crate) field_maps: FieldMaps,
+    pub(crate) timing: Timing,
+    pub(crate) popups: PopupRegistry,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) user: Option<String>,
+}
+
+#[cfg(windows)]
+impl AbcSession {
+    /// Start a session on the running Client4 instance through Windows UI Automation
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Automation)` if UI Automation cannot be initialized or if the
+    /// Client4 window cannot be found
+    pub fn connect() -> Result<Self> {
+        let session = AbcSession::new(UiaBackend::new()?);
+        session.abc_window()?;
+        Ok(session)
+    }
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Start a session that drives Client4 through `backend`. The Client4 window is looked up
+    /// the first time an operation needs it
+    pub fn new(backend: B) -> Self {
+        AbcSession {
+            backend,
+            abc_window: OnceCell::new(),
+            field_maps: field_maps(),
+            timing: Timing::default(),
+            popups: PopupRegistry::default(),
+            retry: RetryPolicy::default(),
+            user: None,
+        }
+        .with_timing(timing())
+    }
+
+    /// Start a session on a Client4 window that has already been found. Only screens and popups
+    /// of the same Client4 process are used, so this also works with several instances open
+    pub fn with_window(backend: B, abc_window: B::Element) -> Self {
+        let mut session = AbcSession::new(backend);
+        session.use_window(Some(abc_window));
+        session
+    }
+
+    /// Use `field_maps` for this session instead of the crate wide [`field_maps`]
+    pub fn with_field_maps(mut self, field_maps: FieldMaps) -> Self {
+        self.field_maps = field_maps;
+        self
+    }
+
+    /// Use `timing` for this session instead of the crate wide [`timing`]
+    pub fn with_timing(mut self, timing: Timing) -> Self {
+        self.backend.set_timeout(timing.matcher_timeout());
+        self.timing = timing;
+        self
+    }
+
+    /// Handle popups according to `popups` for this session instead of
+    /// [`PopupRegistry::default`]
+    pub fn with_popups(mut self, popups: PopupRegistry) -> Self {
+        self.popups = popups;
+        self
+    }
+
+    /// Enter and verify values according to `retry` for this session instead of
+    /// [`RetryPolicy::default`]
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The backend this session drives Client4 through
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// The field maps this session uses to find text boxes
+    pub fn field_maps(&self) -> &FieldMaps {
+        &self.field_maps
+    }
+
+    /// The timing this session waits on Client4 with
+    pub fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    /// The rules this session handles popups with
+    pub fn popups(&self) -> &PopupRegistry {
+        &self.popups
+    }
+
+    /// The policy this session enters and verifies values with
+    pub fn retry(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// The user that logged in through this session, if any
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    /// The Client4 window. Unless one was picked with [`AbcSession::with_window`] or
+    /// [`AbcSession::select_window`], the first one is looked up the first time this is called,
+    /// and reused afterwards
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Automation)` if the Client4 window cannot be found
+    pub fn abc_window(&self) -> Result<&B::Element> {
+        if let Some(abc_window) = self.abc_window.get() {
+            return Ok(abc_window);
+        }
+        let abc_window = self.backend.find_screen(ABC_WINDOW_TITLE)?;
+        Ok(self.abc_window.get_or_init(|| abc_window))
+    }
+
+    /// Poll `condition` until it holds, using the session's timing. The timeout is the delay of
+    /// `operation` (see [`Timing::delay`]), and the polling interval is [`Timing::short`]
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Timeout)` if `condition` does not hold in time. See [`wait_until`]
+    pub fn wait_for(
+        &self,
+        operation: &str,
+        default_timeout_ms: u64,
+        waiting_for: &str,
+        condition: impl FnMut() -> Result<bool>,
+    ) -> Result<()> {
+        wait_until(
+            self.timing.delay(operation, default_timeout_ms),
+            self.timing.short(),
+            waiting_for,
+            condition,
+        )
+    }
+
+    /// Whether a screen whose title contains `title` is open. Titles in [`EXACT_TITLES`] must
+    /// match the whole title of the screen
+    pub fn screen_is_open(&self, title: &str) -> Result<bool> {
+        if EXACT_TITLES.contains(&title) {
+            return Ok(self.backend.screen_named_exists(title)?);
+        }
+        Ok(self.backend.screen_exists(title)?)
+    }
+
+    /// Whether a popup whose title contains `title` is open
+    pub fn popup_is_open(&self, title: &str) -> Result<bool> {
+        Ok(self.backend.find_popup(None, title, 0)?.is_some())
+    }
+
+    /// Whether the text box at `box_index` on `screen` contains exactly `value`
+    pub fn text_box_equals(
+        &self,
+        screen: &B::Element,
+        box_index: usize,
+        value: &str,
+    ) -> Result<bool> {
+        Ok(self.read_text_box_value(screen, box_index)? == value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::FakeBackend;
+    use crate::{AbcError, AbcSession, LoginCredentials, Password, Timing};
+
+    #[test]
+    fn test_login_remembers_user() {
+        let fake = FakeBackend::new();
+        fake.add_screen("ABC Accounting Client");
+        fake.on_keys("{F10}*", |fake, _, _| {
+            let screen = fake.add_screen("Utilities - System Date and Time");
+            fake.add_text_boxes(screen, &["", "", ""]);
+        });
+
+        let mut session = AbcSession::new(fake);
+        assert_eq!(session.user(), None);
+        session.login("clerk", "hunter2").unwrap();
+        assert_eq!(session.user(), Some("clerk"));
+    }
+
+    #[test]
+    fn test_login_reports_popup_text() {
+        let fake = FakeBackend::new();
+        let abc_window = fake.add_screen("ABC Accounting Client");
+        fake.on_keys("{F10}*", |fake, _, _| {
+            let screen = fake.add_screen("Utilities - System Date and Time");
+            fake.add_text_boxes(screen, &["", "", ""]);
+        });
+        fake.on_keys("nobody{Enter}", move |fake, _, _| {
+            let popup = fake.add_element(abc_window, "ThunderRT6FormDC", "Error", "");
+            fake.add_element(popup, "ThunderRT6Label", "Invalid user name", "");
+        });
+
+        let mut session = AbcSession::with_window(fake, abc_window)
+            .with_timing(Timing::default().with_scale(0.1));
+        match session.login("nobody", "hunter2") {
+            Err(AbcError::UnexpectedPopup { popup, text }) => {
+                assert_eq!(popup.message, "Invalid user name");
+                assert!(text.contains("username is probably incorrect"));
+            }
+            other => panic!("expected UnexpectedPopup, got {other:?}"),
+        }
+        assert_eq!(session.user(), None);
+    }
+
+    #[test]
+    fn test_ensure_logged_in() {
+        let fake = FakeBackend::new();
+        fake.add_screen("ABC Accounting Client");
+        let screen = fake.add_screen("Utilities - System Date and Time");
+        fake.add_text_boxes(screen, &["", "", ""]);
+        let credentials = LoginCredentials::new("clerk", Password::new("hunter2"));
+
+        let mut session = AbcSession::new(fake).with_timing(Timing::default().with_scale(0.1));
+        assert!(!session.is_logged_in().unwrap());
+        let opened = session.backend().sent_keys().len();
+        session.ensure_logged_in(&credentials).unwrap();
+        // The login screen read from is the one logged in on
+        let sent_keys = session.backend().sent_keys();
+        let reopened = sent_keys[opened..]
+            .iter()
+            .filter(|(_, keys)| keys == "{F10}*");
+        assert_eq!(reopened.count(), 1);
+        assert_eq!(session.current_user().unwrap().as_deref(), Some("clerk"));
+
+        session.backend().set_text_box(screen, 2, "CLERK");
+        let typed = session.backend().sent_keys().len();
+        session.ensure_logged_in(&credentials).unwrap();
+        let sent_keys = session.backend().sent_keys();
+        assert!(sent_keys[typed..].iter().all(|(_, keys)| keys == "{F10}*"));
+        assert_eq!(session.user(), Some("clerk"));
+    }
+
+    #[test]
+    fn test_switch_user_and_logout() {
+        let fake = FakeBackend::new();
+        fake.add_screen("ABC Accounting Client");
+        let screen = fake.add_screen("Utilities - System Date and Time");
+        fake.add_text_boxes(screen, &["", "", "CLERK"]);
+        let service = LoginCredentials::new("service", Password::new("s3rvice"));
+
+        let mut session = AbcSession::new(fake).with_timing(Timing::default().with_scale(0.1));
+        let previous = session.switch_user(&service).unwrap();
+        assert_eq!(previous.as_deref(), Some("CLERK"));
+        let sent_keys = session.backend().sent_keys();
+        let opened = sent_keys.iter().filter(|(_, keys)| keys == "{F10}*");
+        assert_eq!(opened.count(), 1);
+        assert_eq!(session.current_user().unwrap().as_deref(), Some("service"));
+
+        session.logout().unwrap();
+        // Logging out is clearing the username box, which is what Client4 reads as nobody
+        let (_, keys) = session.backend().sent_keys().pop().unwrap();
+        assert_eq!(keys, "{Delete}{Enter}");
+        assert_eq!(session.user(), None);
+        assert!(!session.is_logged_in().unwrap());
+    }
+}
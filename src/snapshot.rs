@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AbcError, AbcSession, Backend, Bounds, Result};
+#[cfg(windows)]
+use crate::{UIElement, UiaBackend};
+
+/// Class of the text boxes the crate's field indices refer to
+const TEXT_BOX_CLASS: &str = "ThunderRT6TextBox";
+
+/// A copy of an element and everything beneath it, taken with [`snapshot_element`]. Snapshots
+/// can be saved as JSON to document a screen, and compared with [`ElementSnapshot::diff`] to
+/// find out whether a Client4 update moved its fields around
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElementSnapshot {
+    pub classname: String,
+    pub name: String,
+    /// The value of the element, or an empty string if it has none
+    pub value: String,
+    pub bounds: Bounds,
+    /// The name of the UI Automation control type, eg. "Edit"
+    pub control_type: String,
+    /// For a ThunderRT6TextBox, its position among the ThunderRT6TextBox siblings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_box_ordinal: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ElementSnapshot>,
+}
+
+/// One difference between two snapshots, found by [`ElementSnapshot::diff`]. Elements are
+/// identified by a path of class names and their position among siblings of the same class, eg.
+/// `ThunderRT6Form[0]/ThunderRT6TextBox[3]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotChange {
+    /// The element only exists in the newer snapshot
+    Added { path: String },
+    /// The element only exists in the older snapshot
+    Removed { path: String },
+    /// A property of the element differs between the snapshots
+    Changed {
+        path: String,
+        property: &'static str,
+        before: String,
+        after: String,
+    },
+}
+
+impl fmt::Display for SnapshotChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotChange::Added { path } => write!(f, "added {path}"),
+            SnapshotChange::Removed { path } => write!(f, "removed {path}"),
+            SnapshotChange::Changed {
+                path,
+                property,
+                before,
+                after,
+            } => write!(f, "{path}: {property} changed from '{before}' to '{after}'"),
+        }
+    }
+}
+
+impl ElementSnapshot {
+    /// Serialize the snapshot as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| AbcError::Config(e.to_string()))
+    }
+
+    /// Parse a snapshot from JSON written by [`ElementSnapshot::to_json`]
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Config)` if `json` is not a snapshot
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| AbcError::Config(e.to_string()))
+    }
+
+    /// Write the snapshot to `path` as JSON
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Config)` if the file cannot be written
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_json()?)
+            .map_err(|e| AbcError::Config(format!("{}: {e}", path.display())))
+    }
+
+    /// Read a snapshot from a JSON file written by [`ElementSnapshot::save`]
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Config)` if the file cannot be read or parsed
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| AbcError::Config(format!("{}: {e}", path.display())))?;
+        ElementSnapshot::from_json(&json)
+    }
+
+    /// List every difference between this snapshot and `newer`. Children are matched up by
+    /// class name and position among siblings of that class, so a text box that was inserted
+    /// before the others shows up as a change to every text box after it
+    pub fn diff(&self, newer: &ElementSnapshot) -> Vec<SnapshotChange> {
+        let mut changes = Vec::new();
+        diff_helper(self, newer, &format!("{}[0]", self.classname), &mut changes);
+        changes
+    }
+}
+
+fn diff_helper(
+    older: &ElementSnapshot,
+    newer: &ElementSnapshot,
+    path: &str,
+    changes: &mut Vec<SnapshotChange>,
+) {
+    let properties = [
+        ("classname", &older.classname, &newer.classname),
+        ("name", &older.name, &newer.name),
+        ("value", &older.value, &newer.value),
+        ("control_type", &older.control_type, &newer.control_type),
+    ];
+    for (property, before, after) in properties {
+        if before != after {
+            changes.push(SnapshotChange::Changed {
+                path: path.to_string(),
+                property,
+                before: before.clone(),
+                after: after.clone(),
+            });
+        }
+    }
+    if older.bounds != newer.bounds {
+        changes.push(SnapshotChange::Changed {
+            path: path.to_string(),
+            property: "bounds",
+            before: older.bounds.to_string(),
+            after: newer.bounds.to_string(),
+        });
+    }
+
+    let older_children = keyed_children(older);
+    let newer_children = keyed_children(newer);
+    for (key, child) in &older_children {
+        let child_path = format!("{path}/{key}");
+        match newer_children.iter().find(|(k, _)| k == key) {
+            Some((_, newer_child)) => diff_helper(child, newer_child, &child_path, changes),
+            None => changes.push(SnapshotChange::Removed { path: child_path }),
+        }
+    }
+    for (key, _) in &newer_children {
+        if !older_children.iter().any(|(k, _)| k == key) {
+            changes.push(SnapshotChange::Added {
+                path: format!("{path}/{key}"),
+            });
+        }
+    }
+}
+
+/// Pair each child of `snapshot` with its `classname[position]` key
+fn keyed_children(snapshot: &ElementSnapshot) -> Vec<(String, &ElementSnapshot)> {
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    let mut keyed = Vec::new();
+    for child in &snapshot.children {
+        let position = positions.entry(&child.classname).or_default();
+        keyed.push((format!("{}[{position}]", child.classname), child));
+        *position += 1;
+    }
+    keyed
+}
+
+/// Capture `element` and every element beneath it, eg. to document a Client4 screen
+///
+/// # Arguments
+///
+/// * `element` - The `UIElement` to start the snapshot from, such as a screen
+///
+/// # Errors
+///
+/// Will return [`AbcError::Automation`] if an element cannot be read
+#[cfg(windows)]
+pub fn snapshot_element(element: &UIElement) -> Result<ElementSnapshot> {
+    AbcSession::new(UiaBackend::new()?).snapshot_element(element)
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Same as [`snapshot_element`]
+    pub fn snapshot_element(&self, element: &B::Element) -> Result<ElementSnapshot> {
+        self.snapshot_helper(element, None)
+    }
+
+    fn snapshot_helper(
+        &self,
+        element: &B::Element,
+        text_box_ordinal: Option<usize>,
+    ) -> Result<ElementSnapshot> {
+        let mut children = Vec::new();
+        let mut text_boxes = 0;
+        for child in self.backend.children(element)? {
+            let ordinal = match self.backend.classname(&child)?.as_str() {
+                TEXT_BOX_CLASS => {
+                    text_boxes += 1;
+                    Some(text_boxes - 1)
+                }
+                _ => None,
+            };
+            children.push(self.snapshot_helper(&child, ordinal)?);
+        }
+        Ok(ElementSnapshot {
+            classname: self.backend.classname(element)?,
+            name: self.backend.name(element)?,
+            // Most elements do not support the value pattern at all
+            value: self.backend.value(element).unwrap_or_default(),
+            bounds: self.backend.bounds(element)?,
+            control_type: self.backend.control_type(element)?,
+            text_box_ordinal,
+            children,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ElementSnapshot, SnapshotChange};
+    use crate::backend::FakeBackend;
+    use crate::{AbcSession, Bounds};
+
+    #[test]
+    fn test_snapshot_diff() {
+        let fake = FakeBackend::new();
+        let screen = fake.add_screen("Inventory - Items (I)");
+        fake.add_element(screen, "ThunderRT6Label", "Sku", "");
+        let text_boxes = fake.add_text_boxes(screen, &["WIDGET", "BLUE WIDGET"]);
+
+        let session = AbcSession::new(fake);
+        let before = session.snapshot_element(&screen).unwrap();
+        assert_eq!(before.children[2].text_box_ordinal, Some(1));
+        assert_eq!(before.children[0].text_box_ordinal, None);
+        let json = before.to_json().unwrap();
+        assert_eq!(ElementSnapshot::from_json(&json).unwrap(), before);
+
+        let fake = session.backend();
+        fake.set_bounds(
+            text_boxes[1],
+            Bounds {
+                left: 10,
+                top: 20,
+                right: 110,
+                bottom: 40,
+            },
+        );
+        fake.add_text_boxes(screen, &[""]);
+        let after = session.snapshot_element(&screen).unwrap();
+        let changes = before.diff(&after);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(
+            changes[0].to_string(),
+            "ThunderRT6Form[0]/ThunderRT6TextBox[1]: bounds changed from '(0, 0) to (0, 0)' to \
+             '(10, 20) to (110, 40)'"
+        );
+        assert_eq!(
+            changes[1],
+            SnapshotChange::Added {
+                path: "ThunderRT6Form[0]/ThunderRT6TextBox[2]".to_string()
+            }
+        );
+        assert!(after.diff(&after).is_empty());
+    }
+}
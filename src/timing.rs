@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::field_map::load_config;
+use crate::{AbcError, Result, SHORT_WAIT_MS};
+
+/// How long to pause between keystrokes, wait for Client4 to catch up, and search for elements.
+/// The defaults are tuned for a fast machine; slower machines, such as terminal server sessions,
+/// can raise `scale` to slow everything down at once, or override single operations.
+///
+/// Every delay is looked up by the name of the operation it belongs to, so any of them can be
+/// overridden:
+///
+/// | Operation                     | Default              | Used for                              |
+/// |-------------------------------|----------------------|---------------------------------------|
+/// | `send_ctrl_n`                 | `short_wait_ms`      | Pause after Ctrl+N                    |
+/// | `save_changes_popup`          | `short_wait_ms / 2`  | Wait for "Save changes" after Ctrl+N  |
+/// | `error_popup`                 | 1 second             | Wait for an "Error" popup             |
+/// | `add_to_upc_popup`            | `short_wait_ms * 30` | Wait for the "Add to UPC" prompt      |
+/// | `clear_upc`                   | `short_wait_ms * 3`  | Pause after deleting a UPC            |
+/// | `delete_record_popup`         | `short_wait_ms * 30` | Wait for the "Delete Record" prompt   |
+/// | `item_not_found_popup`        | `short_wait_ms * 5`  | Wait for "Not Found" on a new sku     |
+/// | `report_menu`                 | `short_wait_ms * 30` | Wait for a report menu after F10      |
+/// | `report_selected`             | `short_wait_ms * 30` | Wait for the report menu to close     |
+/// | `report_options_screen`       | `short_wait_ms * 30` | Wait for the report options screen    |
+/// | `load_special_reports_screen` | `short_wait_ms * 30` | Wait for the F10-7 screen             |
+/// | `jdf_account_lookup`          | `short_wait_ms`      | Look for a customer's JDF account box |
+/// | `send_invoice_to_jdf`         | 2 seconds            | Wait for Client4 to send to JDF       |
+/// | `popup_close`                 | `short_wait_ms * 30` | Wait for an answered popup to close   |
+/// | `return_to_main_menu`         | `short_wait_ms * 10` | Wait for a screen to close on Escape  |
+/// | `close_login_screen`          | `short_wait_ms * 10` | Wait for the login screen to close    |
+/// | `launch_abc`                  | `startup_timeout_ms` | Wait for Client4 to start             |
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timing {
+    /// Factor every delay and timeout is multiplied by
+    pub scale: f64,
+    /// Base delay in milliseconds between keystrokes. Most other delays are a multiple of this
+    pub short_wait_ms: u64,
+    /// How long, in milliseconds, to keep searching for a screen or text box before giving up
+    pub matcher_timeout_ms: u64,
+    /// Delays in milliseconds that replace the default of an operation. These are scaled too
+    pub overrides: BTreeMap<String, u64>,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing {
+            scale: 1.0,
+            short_wait_ms: SHORT_WAIT_MS,
+            matcher_timeout_ms: SHORT_WAIT_MS * 30,
+            overrides: BTreeMap::new(),
+        }
+    }
+}
+
+impl Timing {
+    /// Multiply every delay by `scale`, returning the updated timing
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Replace the delay of `operation` with `ms`, returning the updated timing
+    pub fn with_override(mut self, operation: &str, ms: u64) -> Self {
+        self.overrides.insert(operation.to_string(), ms);
+        self
+    }
+
+    fn scaled(&self, ms: u64) -> u64 {
+        (ms as f64 * self.scale).round() as u64
+    }
+
+    /// The scaled delay between keystrokes
+    pub fn short(&self) -> u64 {
+        self.scaled(self.short_wait_ms)
+    }
+
+    /// The scaled timeout for finding screens and text boxes
+    pub fn matcher_timeout(&self) -> u64 {
+        self.scaled(self.matcher_timeout_ms)
+    }
+
+    /// The scaled delay of `operation`, which is `default_ms` unless it has been overridden
+    pub fn delay(&self, operation: &str, default_ms: u64) -> u64 {
+        self.scaled(self.overrides.get(operation).copied().unwrap_or(default_ms))
+    }
+
+    /// Parse timing in TOML format. Anything missing keeps its default:
+    ///
+    /// ```toml
+    /// scale = 2.5
+    ///
+    /// [overrides]
+    /// send_invoice_to_jdf = 5000
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Config)` if `toml` cannot be parsed
+    pub fn from_toml_str(toml: &str) -> Result<Self> {
+        toml::from_str(toml).map_err(|e| AbcError::Config(e.to_string()))
+    }
+
+    /// Parse timing in JSON format. See [`Timing::from_toml_str`]
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Config)` if `json` cannot be parsed
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| AbcError::Config(e.to_string()))
+    }
+
+    /// Read timing from a `.toml` or `.json` file
+    ///
+    /// # Errors
+    ///
+    /// Return `Err(AbcError::Config)` if the file cannot be read or parsed, or if its extension
+    /// is neither `toml` nor `json`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        load_config(path.as_ref(), Timing::from_toml_str, Timing::from_json_str)
+    }
+}
+
+static TIMING: RwLock<Option<Timing>> = RwLock::new(None);
+
+/// The timing new sessions, and with them the free functions, start out with. Until
+/// [`set_timing`] is called, this is [`Timing::default`]
+pub fn timing() -> Timing {
+    TIMING
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+/// Replace the timing new sessions start out with, eg. with one loaded through [`Timing::load`]
+pub fn set_timing(timing: Timing) {
+    *TIMING.write().unwrap_or_else(|e| e.into_inner()) = Some(timing);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Timing;
+
+    #[test]
+    fn test_scale_and_overrides() {
+        let timing = Timing::from_toml_str("scale = 2.0\n[overrides]\nset_upc = 500\n").unwrap();
+        assert_eq!(timing.short(), 160);
+        assert_eq!(timing.matcher_timeout(), 4800);
+        assert_eq!(timing.delay("set_upc", 240), 1000);
+        assert_eq!(timing.delay("clear_upc", 240), 480);
+
+        assert!(Timing::from_json_str(r#"{"scale": "fast"}"#).is_err());
+    }
+}
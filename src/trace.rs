@@ -0,0 +1,160 @@
+use std::time::Instant;
+
+use tracing::{debug, debug_span, warn, Span};
+
+use crate::keys::{Key, Keys};
+use crate::{AbcSession, Backend, Result};
+
+/// Stands in for secrets, such as the password typed by [`crate::login`], in traced keystrokes
+pub(crate) const REDACTED: &str = "***";
+
+/// Fields whose contents are never traced
+const SECRET_FIELDS: &[&str] = &["password"];
+
+/// Run `action` inside `span`, then trace how long it took and whether it failed. Every UI
+/// action of the crate runs through here, so a subscriber sees a tree of screen loads, keystroke
+/// sequences, text box reads, verifications and popups, each with its duration in milliseconds
+pub(crate) fn traced<T>(span: Span, action: impl FnOnce() -> Result<T>) -> Result<T> {
+    let _entered = span.enter();
+    let start = Instant::now();
+    let result = action();
+    let elapsed_ms = start.elapsed().as_millis() as u64;
+    match &result {
+        Ok(_) => debug!(elapsed_ms, "done"),
+        Err(e) => warn!(elapsed_ms, error = %e, "failed"),
+    }
+    result
+}
+
+impl<B: Backend> AbcSession<B> {
+    /// Send `keys` to `element`, tracing the keystroke sequence
+    pub(crate) fn send_keys(
+        &self,
+        element: &B::Element,
+        keys: &Keys,
+        interval_ms: u64,
+    ) -> Result<()> {
+        self.send_keys_as(element, keys, keys, interval_ms)
+    }
+
+    /// Send `keys` to `element`, tracing `traced_keys` in their place. When the two differ, the
+    /// error is replaced as well, since it may quote the keys that were sent
+    pub(crate) fn send_keys_as(
+        &self,
+        element: &B::Element,
+        keys: &Keys,
+        traced_keys: &Keys,
+        interval_ms: u64,
+    ) -> Result<()> {
+        traced(
+            debug_span!("send_keys", keys = traced_keys.as_str()),
+            || {
+                self.backend
+                    .send_keys(element, keys.as_str(), interval_ms)
+                    .map_err(|e| match keys == traced_keys {
+                        true => e,
+                        false => e.with_message("could not send the keys"),
+                    })?;
+                Ok(())
+            },
+        )
+    }
+
+    /// Send `keys` to `element` while holding down `modifier`, tracing the keystroke sequence
+    pub(crate) fn hold_send_keys(
+        &self,
+        element: &B::Element,
+        modifier: Key,
+        keys: &Keys,
+        interval_ms: u64,
+    ) -> Result<()> {
+        let holdkeys = modifier.to_string();
+        traced(
+            debug_span!("send_keys", holdkeys, keys = keys.as_str()),
+            || {
+                Ok(self
+                    .backend
+                    .hold_send_keys(element, &holdkeys, keys.as_str(), interval_ms)?)
+            },
+        )
+    }
+
+    /// Record on `span` the title of `screen` and the name the field map gives the text box at
+    /// `box_index`
+    ///
+    /// # Returns
+    ///
+    /// Whether the text box holds a secret, such as a password, whose contents must not be traced.
+    /// A screen whose title cannot be read is assumed to hold one
+    pub(crate) fn record_text_box(
+        &self,
+        span: &Span,
+        screen: &B::Element,
+        box_index: usize,
+    ) -> bool {
+        let Ok(title) = self.backend.name(screen) else {
+            return true;
+        };
+        span.record("screen", title.as_str());
+        let field = self
+            .field_maps
+            .for_screen(&title)
+            .and_then(|map| map.name_of(box_index));
+        if let Some(field) = field {
+            span.record("field", field);
+        }
+        field.is_some_and(|field| SECRET_FIELDS.contains(&field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use crate::backend::FakeBackend;
+    use crate::{AbcSession, Timing};
+
+    /// Collects everything a subscriber writes
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_login_is_traced_without_password() {
+        let fake = FakeBackend::new();
+        fake.add_screen("ABC Accounting Client");
+        let screen = fake.add_screen("Utilities - System Date and Time");
+        fake.add_text_boxes(screen, &["", "", ""]);
+        let session = AbcSession::new(fake).with_timing(Timing::default().with_scale(0.1));
+
+        let output = Output::default();
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let mut session = session;
+            session.login("clerk", "hunter2").unwrap();
+        });
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("login{username=\"clerk\"}"));
+        assert!(output.contains("field=\"username\""));
+        assert!(output.contains("keys=\"{Delete}***{Enter}\""));
+        assert!(output.contains("elapsed_ms="));
+        assert!(!output.contains("hunter2"));
+    }
+}